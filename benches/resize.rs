@@ -0,0 +1,59 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use file_format::FileFormat;
+use std::io::Cursor;
+use thumbnailer::{
+    create_thumbnails_with_options, FilterType, ResizeBackend, ThumbnailOptions, ThumbnailSize,
+};
+
+const PNG_BYTES: &[u8] = include_bytes!("../tests/assets/test.png");
+
+const SIZES: [ThumbnailSize; 3] = [
+    ThumbnailSize::Small,
+    ThumbnailSize::Medium,
+    ThumbnailSize::Large,
+];
+
+fn backends() -> Vec<ResizeBackend> {
+    vec![
+        ResizeBackend::Image,
+        #[cfg(feature = "fast_resize")]
+        ResizeBackend::Simd,
+    ]
+}
+
+fn resize_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("resize");
+
+    for filter in [
+        FilterType::Nearest,
+        FilterType::Triangle,
+        FilterType::Lanczos3,
+    ] {
+        for backend in backends() {
+            let options = ThumbnailOptions {
+                filter: filter.clone(),
+                backend,
+                ..Default::default()
+            };
+            group.bench_with_input(
+                BenchmarkId::new(format!("{backend:?}"), format!("{filter:?}")),
+                &options,
+                |b, options| {
+                    b.iter(|| {
+                        create_thumbnails_with_options(
+                            Cursor::new(PNG_BYTES),
+                            FileFormat::PortableNetworkGraphics,
+                            SIZES,
+                            options,
+                        )
+                        .unwrap()
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, resize_benchmark);
+criterion_main!(benches);
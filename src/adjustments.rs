@@ -0,0 +1,95 @@
+use crate::alpha::{premultiply, unpremultiply};
+use crate::into_color_type;
+use image::{DynamicImage, Rgba32FImage};
+
+/// Luma coefficients of ITU-R BT.709
+const LUMA: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+/// Adjustments applied to the thumbnails after they were resized.
+/// Only the color channels are changed, alpha is kept as it is.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Adjustment {
+    /// Sharpens the image by adding the difference to a blurred copy of it
+    UnsharpMask {
+        /// The standard deviation of the gaussian blur in pixels
+        radius: f32,
+        /// The strength of the sharpening where 1.0 adds the full difference
+        amount: f32,
+        /// The minimal difference to the blurred copy in the range 0..=255
+        /// that gets sharpened, which keeps smooth areas free of noise
+        threshold: u8,
+    },
+    /// Adds the value in the range -1.0..=1.0 to all color channels
+    Brightness(f32),
+    /// Scales the distance to mid grey, 1.0 keeps the image as it is
+    Contrast(f32),
+    /// Scales the distance to the grey value of each pixel, 1.0 keeps the image as it is
+    Saturation(f32),
+    Grayscale,
+}
+
+impl Adjustment {
+    fn apply(&self, image: &mut Rgba32FImage) {
+        match self {
+            Self::UnsharpMask {
+                radius,
+                amount,
+                threshold,
+            } => unsharp_mask(image, *radius, *amount, f32::from(*threshold) / 255.0),
+            Self::Brightness(brightness) => map_colors(image, |[r, g, b]| {
+                [r + brightness, g + brightness, b + brightness]
+            }),
+            Self::Contrast(contrast) => {
+                map_colors(image, |rgb| rgb.map(|value| (value - 0.5) * contrast + 0.5))
+            }
+            Self::Saturation(saturation) => map_colors(image, |rgb| {
+                let luma = luma(rgb);
+                rgb.map(|value| luma + (value - luma) * saturation)
+            }),
+            Self::Grayscale => map_colors(image, |rgb| [luma(rgb); 3]),
+        }
+    }
+}
+
+/// Applies the adjustments in order, keeping the color type of the image
+pub(crate) fn apply_adjustments(image: DynamicImage, adjustments: &[Adjustment]) -> DynamicImage {
+    if adjustments.is_empty() {
+        return image;
+    }
+    let color = image.color();
+    let mut buffer = image.into_rgba32f();
+    for adjustment in adjustments {
+        adjustment.apply(&mut buffer);
+    }
+
+    into_color_type(DynamicImage::ImageRgba32F(buffer), color)
+}
+
+fn luma([r, g, b]: [f32; 3]) -> f32 {
+    r * LUMA[0] + g * LUMA[1] + b * LUMA[2]
+}
+
+fn map_colors<F: Fn([f32; 3]) -> [f32; 3]>(image: &mut Rgba32FImage, map: F) {
+    for pixel in image.pixels_mut() {
+        let [r, g, b, a] = pixel.0;
+        let [r, g, b] = map([r, g, b]);
+        pixel.0 = [r, g, b, a];
+    }
+}
+
+/// Blurs with premultiplied alpha, so the hidden color of transparent pixels
+/// doesn't add halos to the edges of opaque areas
+fn unsharp_mask(image: &mut Rgba32FImage, radius: f32, amount: f32, threshold: f32) {
+    let mut premultiplied = image.clone();
+    premultiply(&mut premultiplied);
+    let mut blurred = image::imageops::blur(&premultiplied, radius.max(0.1));
+    unpremultiply(&mut blurred);
+    for (pixel, blurred) in image.pixels_mut().zip(blurred.pixels()) {
+        for channel in 0..3 {
+            let difference = pixel[channel] - blurred[channel];
+            if difference.abs() >= threshold {
+                pixel[channel] += difference * amount;
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use image::{Rgb, RgbImage, Rgba32FImage, RgbaImage};
+
+const CHECKERBOARD_LIGHT: [u8; 3] = [255, 255, 255];
+const CHECKERBOARD_DARK: [u8; 3] = [204, 204, 204];
+
+/// Background used to flatten transparent images when they are written
+/// in formats without an alpha channel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Background {
+    Color([u8; 3]),
+    /// Light and dark grey squares with the given size in pixels
+    Checkerboard(u32),
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Self::Color([255, 255, 255])
+    }
+}
+
+impl Background {
+    fn color_at(&self, x: u32, y: u32) -> [u8; 3] {
+        match self {
+            Self::Color(color) => *color,
+            Self::Checkerboard(size) => {
+                let size = (*size).max(1);
+                if (x / size + y / size).is_multiple_of(2) {
+                    CHECKERBOARD_LIGHT
+                } else {
+                    CHECKERBOARD_DARK
+                }
+            }
+        }
+    }
+}
+
+/// Composites the image over the background
+pub(crate) fn flatten(image: &RgbaImage, background: Background) -> RgbImage {
+    RgbImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, a] = image.get_pixel(x, y).0;
+        let alpha = u16::from(a);
+        let background = background.color_at(x, y);
+        let blend = |foreground: u8, background: u8| {
+            let value = u16::from(foreground) * alpha + u16::from(background) * (255 - alpha) + 127;
+            (value / 255) as u8
+        };
+
+        Rgb([
+            blend(r, background[0]),
+            blend(g, background[1]),
+            blend(b, background[2]),
+        ])
+    })
+}
+
+/// Multiplies the color channels with the alpha channel
+pub(crate) fn premultiply(image: &mut Rgba32FImage) {
+    for pixel in image.pixels_mut() {
+        let [r, g, b, a] = pixel.0;
+        pixel.0 = [r * a, g * a, b * a, a];
+    }
+}
+
+/// Divides the color channels by the alpha channel
+pub(crate) fn unpremultiply(image: &mut Rgba32FImage) {
+    for pixel in image.pixels_mut() {
+        let [r, g, b, a] = pixel.0;
+        // filters with negative lobes can overshoot the valid range
+        let a = a.clamp(0.0, 1.0);
+        pixel.0 = if a > 0.0 {
+            [r / a, g / a, b / a, a]
+        } else {
+            [0.0; 4]
+        };
+    }
+}
@@ -0,0 +1,119 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::{resize_images, OutputFormat, Thumbnail, ThumbnailSize};
+
+/// Thumbnails aren't downscaled below this size on their longer edge
+const MIN_EDGE: u32 = 16;
+/// The factor the dimensions are reduced by in every downscaling step
+const DOWNSCALE_FACTOR: f32 = 0.8;
+
+/// The maximum size of an encoded thumbnail
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ByteBudget {
+    /// The maximum number of bytes of the encoded image
+    pub max_bytes: usize,
+    /// The lowest quality in the range 0..=100 used for lossy formats
+    pub min_quality: u8,
+    /// Reduces the dimensions of the thumbnail when it doesn't fit
+    /// into the budget with the lowest quality
+    pub downscale: bool,
+}
+
+impl ByteBudget {
+    /// Creates a budget with a minimum quality of 10 that keeps the dimensions
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            min_quality: 10,
+            downscale: false,
+        }
+    }
+}
+
+/// A thumbnail encoded within a byte budget
+#[derive(Clone, Debug)]
+pub struct BudgetedImage {
+    pub data: Vec<u8>,
+    /// The quality the image was encoded with or `None` for formats without a quality setting
+    pub quality: Option<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Thumbnail {
+    /// Encodes the image with the highest quality that fits into the byte budget.
+    /// The quality of the format is used as the upper limit.
+    pub fn encode_within_budget(
+        &self,
+        format: &OutputFormat,
+        budget: &ByteBudget,
+    ) -> ThumbResult<BudgetedImage> {
+        let mut thumbnail = self.clone();
+
+        loop {
+            if let Some(image) = thumbnail.encode_highest_quality(format, budget)? {
+                return Ok(image);
+            }
+            let (width, height) = thumbnail.size();
+            if !budget.downscale || width.max(height) <= MIN_EDGE {
+                return Err(ThumbError::ExceedsByteBudget(budget.max_bytes));
+            }
+            thumbnail = thumbnail.downscaled();
+        }
+    }
+
+    /// Binary searches the highest quality that fits into the budget
+    fn encode_highest_quality(
+        &self,
+        format: &OutputFormat,
+        budget: &ByteBudget,
+    ) -> ThumbResult<Option<BudgetedImage>> {
+        let Some(max_quality) = format.quality() else {
+            let data = self.encode_to_vec(format)?;
+            return Ok((data.len() <= budget.max_bytes).then(|| self.budgeted_image(data, None)));
+        };
+        let mut low = i16::from(budget.min_quality.min(max_quality));
+        let mut high = i16::from(max_quality);
+        let mut best = None;
+
+        while low <= high {
+            let quality = ((low + high) / 2) as u8;
+            let data = self.encode_to_vec(&format.with_quality(quality))?;
+            if data.len() <= budget.max_bytes {
+                best = Some(self.budgeted_image(data, Some(quality)));
+                low = i16::from(quality) + 1;
+            } else {
+                high = i16::from(quality) - 1;
+            }
+        }
+
+        Ok(best)
+    }
+
+    fn encode_to_vec(&self, format: &OutputFormat) -> ThumbResult<Vec<u8>> {
+        let mut data = Vec::new();
+        self.encode(format, &mut data)?;
+
+        Ok(data)
+    }
+
+    fn budgeted_image(&self, data: Vec<u8>, quality: Option<u8>) -> BudgetedImage {
+        let (width, height) = self.size();
+        BudgetedImage {
+            data,
+            quality,
+            width,
+            height,
+        }
+    }
+
+    /// Returns a copy with the dimensions reduced by the downscale factor
+    /// that is resized with the options the thumbnail was created with
+    fn downscaled(mut self) -> Self {
+        let (width, height) = self.size();
+        let scale = |value: u32| ((value as f32 * DOWNSCALE_FACTOR).round() as u32).max(1);
+        let size = ThumbnailSize::Custom((scale(width), scale(height)));
+        self.inner = resize_images(&self.inner, &[size], &self.options).remove(0);
+
+        self
+    }
+}
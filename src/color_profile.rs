@@ -0,0 +1,266 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::tone_mapping::srgb_encode;
+use image::{DynamicImage, ImageBuffer, Pixel, RgbImage};
+#[cfg(feature = "avif")]
+use moxcms::{CicpColorPrimaries, CicpProfile, MatrixCoefficients, TransferCharacteristics};
+use moxcms::{
+    CmsError, DataColorSpace, Layout, ToneCurveEvaluator, ToneReprCurve, TransformOptions,
+};
+
+/// Color profiles images can be converted to before they are resized.
+/// Images without an embedded icc profile are treated as srgb.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ColorProfile {
+    #[default]
+    Srgb,
+    DisplayP3,
+    AdobeRgb,
+    /// The bytes of an rgb icc profile
+    Icc(Vec<u8>),
+}
+
+impl ColorProfile {
+    fn to_cms_profile(&self) -> ThumbResult<moxcms::ColorProfile> {
+        let profile = match self {
+            Self::Srgb => moxcms::ColorProfile::new_srgb(),
+            Self::DisplayP3 => moxcms::ColorProfile::new_display_p3(),
+            Self::AdobeRgb => moxcms::ColorProfile::new_adobe_rgb(),
+            Self::Icc(bytes) => moxcms::ColorProfile::new_from_slice(bytes)
+                .map_err(|_| ThumbError::InvalidColorProfile)?,
+        };
+        if profile.color_space != DataColorSpace::Rgb {
+            return Err(ThumbError::InvalidColorProfile);
+        }
+
+        Ok(profile)
+    }
+
+    /// Returns the icc profile that can be embedded into encoded images
+    pub fn icc_bytes(&self) -> ThumbResult<Vec<u8>> {
+        match self {
+            Self::Icc(bytes) => Ok(bytes.clone()),
+            _ => self
+                .to_cms_profile()?
+                .encode()
+                .map_err(|_| ThumbError::InvalidColorProfile),
+        }
+    }
+}
+
+/// Converts an image from its embedded icc profile (or srgb if it doesn't have one) to the
+/// target profile. Broken or unsupported embedded profiles are ignored and images that can't
+/// be converted are kept as they are. Float images are linear light and only converted to the
+/// primaries of the target, so hdr values are kept. They are encoded with the transfer curve
+/// of the target when they are written.
+pub(crate) fn convert_color_profile(
+    image: DynamicImage,
+    icc_profile: Option<&[u8]>,
+    target: &ColorProfile,
+) -> ThumbResult<DynamicImage> {
+    let source = icc_profile
+        .and_then(|bytes| moxcms::ColorProfile::new_from_slice(bytes).ok())
+        .filter(|profile| profile.color_space == DataColorSpace::Rgb);
+    if source.is_none() && *target == ColorProfile::Srgb {
+        return Ok(image);
+    }
+    let source = source.unwrap_or_else(moxcms::ColorProfile::new_srgb);
+    let target = target.to_cms_profile()?;
+
+    // grayscale images are expanded so they can be represented in the target profile
+    let mut image = match image {
+        DynamicImage::ImageLuma8(_) => DynamicImage::ImageRgb8(image.into_rgb8()),
+        DynamicImage::ImageLumaA8(_) => DynamicImage::ImageRgba8(image.into_rgba8()),
+        DynamicImage::ImageLuma16(_) => DynamicImage::ImageRgb16(image.into_rgb16()),
+        DynamicImage::ImageLumaA16(_) => DynamicImage::ImageRgba16(image.into_rgba16()),
+        image => image,
+    };
+    if let Err(e) = convert_pixels(&mut image, &source, &target) {
+        log::warn!("keeping the colors of the image as the color profile can't be applied: {e}");
+    }
+
+    Ok(image)
+}
+
+/// Converts the pixels in place. The image is left untouched if the conversion fails.
+fn convert_pixels(
+    image: &mut DynamicImage,
+    source: &moxcms::ColorProfile,
+    target: &moxcms::ColorProfile,
+) -> Result<(), CmsError> {
+    let options = TransformOptions::default();
+
+    match image {
+        DynamicImage::ImageRgb8(image) => {
+            let transform =
+                source.create_transform_8bit(Layout::Rgb, target, Layout::Rgb, options)?;
+            transform_in_place(image, |src, dst| transform.transform(src, dst))
+        }
+        DynamicImage::ImageRgba8(image) => {
+            let transform =
+                source.create_transform_8bit(Layout::Rgba, target, Layout::Rgba, options)?;
+            transform_in_place(image, |src, dst| transform.transform(src, dst))
+        }
+        DynamicImage::ImageRgb16(image) => {
+            let transform =
+                source.create_transform_16bit(Layout::Rgb, target, Layout::Rgb, options)?;
+            transform_in_place(image, |src, dst| transform.transform(src, dst))
+        }
+        DynamicImage::ImageRgba16(image) => {
+            let transform =
+                source.create_transform_16bit(Layout::Rgba, target, Layout::Rgba, options)?;
+            transform_in_place(image, |src, dst| transform.transform(src, dst))
+        }
+        DynamicImage::ImageRgb32F(image) => {
+            let matrix = primaries_matrix(source, target)?;
+            for pixel in image.pixels_mut() {
+                pixel.0 = apply_matrix(&matrix, pixel.0);
+            }
+            Ok(())
+        }
+        DynamicImage::ImageRgba32F(image) => {
+            let matrix = primaries_matrix(source, target)?;
+            for pixel in image.pixels_mut() {
+                let [r, g, b, a] = pixel.0;
+                let [r, g, b] = apply_matrix(&matrix, [r, g, b]);
+                pixel.0 = [r, g, b, a];
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The transfer curve of a color profile that encodes linear float images
+pub(crate) struct TransferCurve(Option<[Box<dyn ToneCurveEvaluator + Send + Sync>; 3]>);
+
+impl TransferCurve {
+    /// Uses the curves of the profile and falls back to the srgb curve
+    /// if the profile doesn't describe them with a tone reproduction curve
+    pub(crate) fn new(profile: &ColorProfile) -> Self {
+        if *profile == ColorProfile::Srgb {
+            return Self(None);
+        }
+        let curves = profile.to_cms_profile().ok().and_then(|profile| {
+            let curve = |trc: &Option<ToneReprCurve>| trc.as_ref()?.make_gamma_evaluator().ok();
+            Some([
+                curve(&profile.red_trc)?,
+                curve(&profile.green_trc)?,
+                curve(&profile.blue_trc)?,
+            ])
+        });
+
+        Self(curves)
+    }
+
+    /// Encodes linear values in the range 0..=1
+    pub(crate) fn encode(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let Some(curves) = &self.0 else {
+            return rgb.map(srgb_encode);
+        };
+
+        [0, 1, 2].map(|i| curves[i].evaluate_value(rgb[i]).clamp(0.0, 1.0))
+    }
+}
+
+/// Returns an icc profile for sdr images with the primaries and transfer function signalled
+/// by cicp code points. Bt.709 primaries are treated as srgb and don't need a profile.
+#[cfg(feature = "avif")]
+pub(crate) fn cicp_icc_profile(primaries: u8, transfer: u8) -> Option<Vec<u8>> {
+    let color_primaries = CicpColorPrimaries::try_from(primaries).ok()?;
+    if matches!(
+        color_primaries,
+        CicpColorPrimaries::Reserved | CicpColorPrimaries::Bt709 | CicpColorPrimaries::Unspecified
+    ) {
+        return None;
+    }
+    // unspecified and hdr transfer functions fall back to the srgb curve
+    let transfer_characteristics = TransferCharacteristics::try_from(transfer)
+        .ok()
+        .filter(|transfer| {
+            !matches!(
+                transfer,
+                TransferCharacteristics::Reserved
+                    | TransferCharacteristics::Unspecified
+                    | TransferCharacteristics::Smpte2084
+                    | TransferCharacteristics::Hlg
+            )
+        })
+        .unwrap_or(TransferCharacteristics::Srgb);
+    let profile = moxcms::ColorProfile::new_from_cicp(CicpProfile {
+        color_primaries,
+        transfer_characteristics,
+        matrix_coefficients: MatrixCoefficients::Bt709,
+        full_range: true,
+    });
+
+    profile.encode().ok()
+}
+
+/// Returns the matrix converting linear light between the primaries of the profiles.
+/// The transfer functions are skipped as they would clip values outside of 0..=1.
+fn primaries_matrix(
+    source: &moxcms::ColorProfile,
+    target: &moxcms::ColorProfile,
+) -> Result<[[f32; 3]; 3], CmsError> {
+    if !source.is_matrix_shaper() || !target.is_matrix_shaper() {
+        return Err(CmsError::UnsupportedProfileConnection);
+    }
+
+    Ok(source
+        .transform_matrix(target)
+        .v
+        .map(|row| row.map(|value| value as f32)))
+}
+
+fn apply_matrix(matrix: &[[f32; 3]; 3], rgb: [f32; 3]) -> [f32; 3] {
+    matrix.map(|row| row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2])
+}
+
+/// Converts cmyk pixels (4 bytes per pixel, 0 meaning no ink) to the target profile
+/// using the given cmyk icc profile
+pub(crate) fn convert_cmyk_image(
+    width: u32,
+    height: u32,
+    cmyk: &[u8],
+    icc_profile: &[u8],
+    target: &ColorProfile,
+) -> ThumbResult<RgbImage> {
+    let source =
+        moxcms::ColorProfile::new_from_slice(icc_profile).map_err(|_| ThumbError::Decode)?;
+    if source.color_space != DataColorSpace::Cmyk {
+        return Err(ThumbError::Decode);
+    }
+    let transform = source
+        .create_transform_8bit(
+            Layout::Rgba,
+            &target.to_cms_profile()?,
+            Layout::Rgb,
+            TransformOptions::default(),
+        )
+        .map_err(|_| ThumbError::Decode)?;
+    let mut rgb = vec![0u8; cmyk.len() / 4 * 3];
+    transform
+        .transform(cmyk, &mut rgb)
+        .map_err(|_| ThumbError::Decode)?;
+
+    ImageBuffer::from_raw(width, height, rgb).ok_or(ThumbError::Decode)
+}
+
+/// Returns whether the icc profile describes a cmyk color space
+pub(crate) fn is_cmyk_profile(icc_profile: &[u8]) -> bool {
+    moxcms::ColorProfile::new_from_slice(icc_profile)
+        .map(|profile| profile.color_space == DataColorSpace::Cmyk)
+        .unwrap_or(false)
+}
+
+fn transform_in_place<P, F>(
+    image: &mut ImageBuffer<P, Vec<P::Subpixel>>,
+    transform: F,
+) -> Result<(), CmsError>
+where
+    P: Pixel,
+    F: Fn(&[P::Subpixel], &mut [P::Subpixel]) -> Result<(), CmsError>,
+{
+    let source = image.as_raw().clone();
+    transform(&source, image)
+}
@@ -0,0 +1,6 @@
+/// Copyright notices of the source image that can be kept in the written thumbnails
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Copyright {
+    pub copyright: Option<String>,
+    pub artist: Option<String>,
+}
@@ -0,0 +1,241 @@
+/// Formats thumbnails can be encoded in with their options
+#[derive(Clone, Debug, PartialEq)]
+pub enum OutputFormat {
+    Png(PngOptions),
+    /// Jpeg with transparent areas flattened onto the background of the thumbnail
+    Jpeg(JpegOptions),
+    #[cfg(feature = "webp")]
+    Webp(WebpOptions),
+    /// Avif signalling srgb, which fails for thumbnails converted to other color profiles
+    #[cfg(feature = "avif")]
+    Avif(AvifOptions),
+    Gif,
+    #[cfg(feature = "qoi")]
+    Qoi,
+    #[cfg(feature = "bmp")]
+    Bmp,
+}
+
+/// Options used when writing thumbnails in the png format
+#[derive(Clone, Debug, PartialEq)]
+pub struct PngOptions {
+    /// The deflate compression level in the range 0..=9 where 0 doesn't compress at all
+    pub compression: u8,
+    /// The filter applied to the rows before compressing them
+    pub filter: PngFilter,
+    /// Stores the image losslessly as rgb, grayscale or with a palette
+    /// when it doesn't use all channels or has at most 256 colors
+    pub reduce: bool,
+    /// Reduces the colors to a palette, losing information
+    pub quantization: Option<PngQuantization>,
+    /// Keeps 16 bits per channel if the source image has more than 8 bits per channel.
+    /// This doesn't apply to quantized images.
+    pub high_bit_depth: bool,
+}
+
+impl Default for PngOptions {
+    fn default() -> Self {
+        Self {
+            compression: 6,
+            filter: PngFilter::default(),
+            reduce: true,
+            quantization: None,
+            high_bit_depth: false,
+        }
+    }
+}
+
+/// Filters applied to the rows of png images to make them compress better
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PngFilter {
+    None,
+    Sub,
+    Up,
+    Average,
+    Paeth,
+    /// Selects the filter that works best for every row
+    #[default]
+    Adaptive,
+}
+
+/// Lossy reduction of png images to a palette
+#[derive(Clone, Debug, PartialEq)]
+pub struct PngQuantization {
+    /// The number of colors in the range 2..=256
+    pub colors: u16,
+    /// The strength of the error diffusion dithering in the range 0.0..=1.0
+    /// where 0.0 maps every pixel to the nearest palette color
+    pub dithering: f32,
+}
+
+impl Default for PngQuantization {
+    fn default() -> Self {
+        Self {
+            colors: 256,
+            dithering: 1.0,
+        }
+    }
+}
+
+/// Options used when writing thumbnails in the jpeg format.
+/// The color profile is embedded when enabled with `ThumbnailOptions::embed_color_profile`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JpegOptions {
+    /// The quality in the range 1..=100
+    pub quality: u8,
+    /// Stores the image in multiple scans of increasing detail
+    pub progressive: bool,
+    pub subsampling: ChromaSubsampling,
+    /// Computes huffman tables for the image instead of using the standard ones,
+    /// which makes files smaller but encoding slower
+    pub optimize_huffman_tables: bool,
+    /// Keeps the copyright and artist of the source image in the exif data.
+    /// They are only read when enabled with `ThumbnailOptions::read_copyright`.
+    pub keep_copyright: bool,
+}
+
+impl Default for JpegOptions {
+    fn default() -> Self {
+        Self {
+            quality: 80,
+            progressive: false,
+            subsampling: ChromaSubsampling::default(),
+            optimize_huffman_tables: false,
+            keep_copyright: false,
+        }
+    }
+}
+
+/// The resolution of the color channels relative to the brightness
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChromaSubsampling {
+    /// Full resolution
+    #[default]
+    Yuv444,
+    /// Half the horizontal resolution
+    Yuv422,
+    /// Half the horizontal and vertical resolution
+    Yuv420,
+}
+
+/// Options used when writing thumbnails in the webp format
+#[derive(Clone, Debug, PartialEq)]
+pub struct WebpOptions {
+    /// The quality in the range 0.0..=100.0. For lossless images it's the
+    /// effort spent on compressing them instead.
+    pub quality: f32,
+    /// Writes the image without losing any information
+    pub lossless: bool,
+    /// Preprocesses lossless images to compress better in the range 0..=100
+    /// where 100 turns the preprocessing off
+    pub near_lossless: u8,
+    /// The tradeoff between speed and size in the range 0..=6 where 6 is the slowest
+    pub method: u8,
+    /// The quality of the alpha channel of lossy images in the range 0..=100
+    pub alpha_quality: u8,
+}
+
+impl Default for WebpOptions {
+    fn default() -> Self {
+        Self {
+            quality: 70.0,
+            lossless: false,
+            near_lossless: 100,
+            method: 4,
+            alpha_quality: 100,
+        }
+    }
+}
+
+#[cfg(feature = "webp")]
+impl WebpOptions {
+    pub(crate) fn to_config(&self) -> Option<webp::WebPConfig> {
+        let mut config = webp::WebPConfig::new().ok()?;
+        config.lossless = i32::from(self.lossless);
+        config.quality = self.quality.clamp(0.0, 100.0);
+        config.method = i32::from(self.method.min(6));
+        config.alpha_quality = i32::from(self.alpha_quality.min(100));
+        config.near_lossless = i32::from(self.near_lossless.min(100));
+
+        Some(config)
+    }
+}
+
+/// Options used when writing thumbnails in the avif format
+#[derive(Clone, Debug, PartialEq)]
+pub struct AvifOptions {
+    /// The quality of the color channels in the range 1.0..=100.0
+    pub quality: f32,
+    /// The quality of the alpha channel in the range 1.0..=100.0
+    pub alpha_quality: f32,
+    /// The tradeoff between speed and size in the range 1..=10 where 1 is the slowest
+    pub speed: u8,
+}
+
+impl Default for AvifOptions {
+    fn default() -> Self {
+        Self {
+            quality: 70.0,
+            alpha_quality: 90.0,
+            speed: 6,
+        }
+    }
+}
+
+#[cfg(feature = "avif")]
+impl AvifOptions {
+    pub(crate) fn to_encoder(&self) -> ravif::Encoder<'static> {
+        ravif::Encoder::new()
+            .with_quality(self.quality.clamp(1.0, 100.0))
+            .with_alpha_quality(self.alpha_quality.clamp(1.0, 100.0))
+            .with_speed(self.speed.clamp(1, 10))
+    }
+}
+
+impl OutputFormat {
+    /// Returns the mime type of the encoded images
+    pub const fn mime_type(&self) -> &'static str {
+        match self {
+            Self::Png(_) => "image/png",
+            Self::Jpeg(_) => "image/jpeg",
+            #[cfg(feature = "webp")]
+            Self::Webp(_) => "image/webp",
+            #[cfg(feature = "avif")]
+            Self::Avif(_) => "image/avif",
+            Self::Gif => "image/gif",
+            #[cfg(feature = "qoi")]
+            Self::Qoi => "image/qoi",
+            #[cfg(feature = "bmp")]
+            Self::Bmp => "image/bmp",
+        }
+    }
+
+    /// Returns the quality of lossy formats in the range 0..=100
+    pub(crate) fn quality(&self) -> Option<u8> {
+        match self {
+            Self::Jpeg(options) => Some(options.quality.min(100)),
+            #[cfg(feature = "webp")]
+            Self::Webp(options) if !options.lossless => {
+                Some(options.quality.clamp(0.0, 100.0) as u8)
+            }
+            #[cfg(feature = "avif")]
+            Self::Avif(options) => Some(options.quality.clamp(1.0, 100.0) as u8),
+            _ => None,
+        }
+    }
+
+    /// Returns the format with the quality of lossy formats replaced
+    pub(crate) fn with_quality(&self, quality: u8) -> Self {
+        let mut format = self.clone();
+        match &mut format {
+            Self::Jpeg(options) => options.quality = quality.max(1),
+            #[cfg(feature = "webp")]
+            Self::Webp(options) => options.quality = f32::from(quality),
+            #[cfg(feature = "avif")]
+            Self::Avif(options) => options.quality = f32::from(quality.max(1)),
+            _ => {}
+        }
+
+        format
+    }
+}
@@ -13,6 +13,15 @@ pub enum ThumbError {
     Unsupported(FileFormat),
     NullVideo,
     FFMPEG(String),
+    EncryptedPdf,
+    MalformedPdf,
+    Pdftoppm(String),
+    InvalidColorProfile,
+    Webp(String),
+    Avif(String),
+    ExceedsByteBudget(usize),
+    InvalidPlaceholder(String),
+    InvalidSize(String),
 }
 
 impl Display for ThumbError {
@@ -24,6 +33,17 @@ impl Display for ThumbError {
             Self::Unsupported(mime) => write!(f, "Unsupported media type {mime}"),
             Self::NullVideo => write!(f, "no video data found in file"),
             Self::FFMPEG(e) => write!(f, "ffmpeg error: {e}"),
+            Self::EncryptedPdf => write!(f, "the pdf document requires a password"),
+            Self::MalformedPdf => write!(f, "the pdf document is malformed"),
+            Self::Pdftoppm(e) => write!(f, "pdftoppm error: {e}"),
+            Self::InvalidColorProfile => write!(f, "the color profile is invalid"),
+            Self::Webp(e) => write!(f, "webp error: {e}"),
+            Self::Avif(e) => write!(f, "avif error: {e}"),
+            Self::ExceedsByteBudget(max_bytes) => {
+                write!(f, "the image doesn't fit into {max_bytes} bytes")
+            }
+            Self::InvalidPlaceholder(e) => write!(f, "invalid placeholder: {e}"),
+            Self::InvalidSize(size) => write!(f, "invalid thumbnail size {size}"),
         }
     }
 }
@@ -49,3 +69,27 @@ impl From<image::error::ImageError> for ThumbError {
         Self::Image(e)
     }
 }
+
+impl From<png::EncodingError> for ThumbError {
+    fn from(e: png::EncodingError) -> Self {
+        match e {
+            png::EncodingError::IoError(e) => Self::IO(e),
+            e => Self::Image(ImageError::Encoding(image::error::EncodingError::new(
+                image::ImageFormat::Png.into(),
+                e,
+            ))),
+        }
+    }
+}
+
+impl From<jpeg_encoder::EncodingError> for ThumbError {
+    fn from(e: jpeg_encoder::EncodingError) -> Self {
+        match e {
+            jpeg_encoder::EncodingError::IoError(e) => Self::IO(e),
+            e => Self::Image(ImageError::Encoding(image::error::EncodingError::new(
+                image::ImageFormat::Jpeg.into(),
+                e,
+            ))),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use crate::FilterType;
+use fast_image_resize::{ResizeAlg, ResizeOptions, Resizer};
+use image::DynamicImage;
+
+impl FilterType {
+    /// Returns the convolution matching the filter of the image crate.
+    /// Nearest neighbor sampling samples different pixels, so it isn't replaced.
+    const fn resize_alg(&self) -> Option<ResizeAlg> {
+        use fast_image_resize::FilterType as Fir;
+        let filter = match self {
+            Self::Nearest => return None,
+            Self::Triangle => Fir::Bilinear,
+            Self::CatmullRom => Fir::CatmullRom,
+            Self::Gaussian => Fir::Gaussian,
+            Self::Lanczos3 => Fir::Lanczos3,
+        };
+
+        Some(ResizeAlg::Convolution(filter))
+    }
+}
+
+/// Resizes the image with the SIMD accelerated convolution of fast_image_resize.
+/// Returns `None` for filters and pixel types it doesn't support.
+pub(crate) fn resize_exact(
+    image: &DynamicImage,
+    width: u32,
+    height: u32,
+    filter: &FilterType,
+) -> Option<DynamicImage> {
+    let resize_alg = filter.resize_alg()?;
+    let mut resized = DynamicImage::new(width, height, image.color());
+    // images with transparency are already premultiplied before resizing
+    let options = ResizeOptions::new().resize_alg(resize_alg).use_alpha(false);
+    Resizer::new().resize(image, &mut resized, &options).ok()?;
+
+    Some(resized)
+}
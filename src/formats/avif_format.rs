@@ -0,0 +1,328 @@
+use super::heif_format::read_heif_image;
+use crate::color_profile::{cicp_icc_profile, convert_color_profile, ColorProfile};
+use crate::error::{ThumbError, ThumbResult};
+use crate::tone_mapping::{hdr_to_linear, HdrTransfer};
+use crate::utils::heif_container::apply_transformations;
+use file_format::FileFormat;
+use image::{DynamicImage, ImageBuffer, Rgba};
+use rav1d::include::dav1d::data::Dav1dData;
+use rav1d::include::dav1d::dav1d::{Dav1dContext, Dav1dSettings};
+use rav1d::include::dav1d::headers::*;
+use rav1d::include::dav1d::picture::Dav1dPicture;
+use rav1d::src::lib::{
+    dav1d_close, dav1d_data_create, dav1d_data_unref, dav1d_default_settings, dav1d_get_picture,
+    dav1d_open, dav1d_picture_unref, dav1d_send_data,
+};
+use std::io::Read;
+use std::mem::MaybeUninit;
+use std::ptr::{self, NonNull};
+
+/// Reads an avif image by decoding its av1 items with rav1d and converts it from the
+/// color space signalled by the av1 sequence header to the given color profile.
+/// Grid images aren't supported by the parser, so they are decoded by ffmpeg instead.
+pub fn read_avif_image<R: Read>(
+    mut reader: R,
+    profile: &ColorProfile,
+) -> ThumbResult<DynamicImage> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+    let avif = match avif_parse::read_avif(&mut &buf[..]) {
+        Ok(avif) => avif,
+        Err(avif_parse::Error::Unsupported(_)) => {
+            let image = read_heif_image(&buf[..], FileFormat::Av1ImageFileFormat)?;
+            return convert_color_profile(image, None, profile);
+        }
+        Err(_) => return Err(ThumbError::Decode),
+    };
+
+    let mut decoder = Decoder::new()?;
+    let color = decoder.decode(&avif.primary_item)?;
+    let alpha = match &avif.alpha_item {
+        Some(item) => Some(decoder.decode(item)?),
+        None => None,
+    };
+    let image = to_image(&color, alpha.as_ref(), avif.premultiplied_alpha)?;
+    let image = apply_transformations(image, &buf);
+
+    match color.hdr_transfer() {
+        // hdr images are converted to linear bt.709 light like hdr pngs
+        Some(transfer) => {
+            let image = DynamicImage::ImageRgba16(image.into_rgba16());
+            convert_color_profile(hdr_to_linear(image, Some(transfer)), None, profile)
+        }
+        None => {
+            let icc_profile = cicp_icc_profile(color.primaries, color.transfer);
+            convert_color_profile(image, icc_profile.as_deref(), profile)
+        }
+    }
+}
+
+/// A decoded frame with its planes copied out of the decoder
+struct Frame {
+    width: usize,
+    height: usize,
+    depth: u32,
+    layout: Dav1dPixelLayout,
+    matrix: Dav1dMatrixCoefficients,
+    /// The cicp code points of the primaries and the transfer function
+    primaries: u8,
+    transfer: u8,
+    full_range: bool,
+    planes: [Vec<u16>; 3],
+}
+
+impl Frame {
+    /// Returns the hdr transfer function if it's signalled together with the
+    /// bt.2020 primaries, which are the only ones hdr images are converted from
+    fn hdr_transfer(&self) -> Option<HdrTransfer> {
+        if u32::from(self.primaries) != DAV1D_COLOR_PRI_BT2020 {
+            return None;
+        }
+        match u32::from(self.transfer) {
+            DAV1D_TRC_SMPTE2084 => Some(HdrTransfer::Pq),
+            DAV1D_TRC_HLG => Some(HdrTransfer::Hlg),
+            _ => None,
+        }
+    }
+
+    /// Returns the sample of a plane at the given luma coordinates normalized to 0..=1
+    fn sample(&self, plane: usize, x: usize, y: usize) -> f32 {
+        let (x, y, width) = if plane == 0 {
+            (x, y, self.width)
+        } else {
+            let (ss_x, ss_y) = subsampling(self.layout);
+            (x >> ss_x, y >> ss_y, (self.width + ss_x) >> ss_x)
+        };
+        let value = f32::from(self.planes[plane][y * width + x]);
+        let scale = (1 << (self.depth - 8)) as f32;
+
+        match (plane, self.full_range) {
+            (_, true) => value / ((1 << self.depth) - 1) as f32,
+            (0, false) => (value - 16.0 * scale) / (219.0 * scale),
+            (_, false) => (value - 16.0 * scale) / (224.0 * scale),
+        }
+    }
+
+    fn rgb(&self, x: usize, y: usize) -> [f32; 3] {
+        let luma = self.sample(0, x, y);
+        if self.layout == DAV1D_PIXEL_LAYOUT_I400 {
+            return [luma; 3];
+        }
+        let cb = self.sample(1, x, y) - 0.5;
+        let cr = self.sample(2, x, y) - 0.5;
+
+        if self.matrix == DAV1D_MC_IDENTITY {
+            // the planes are stored as gbr
+            return [cr + 0.5, luma, cb + 0.5];
+        }
+        let (kr, kb) = match self.matrix {
+            DAV1D_MC_BT709 => (0.2126, 0.0722),
+            DAV1D_MC_FCC => (0.30, 0.11),
+            DAV1D_MC_SMPTE240 => (0.212, 0.087),
+            DAV1D_MC_BT2020_NCL | DAV1D_MC_BT2020_CL => (0.2627, 0.0593),
+            _ => (0.299, 0.114),
+        };
+        let r = luma + 2.0 * (1.0 - kr) * cr;
+        let b = luma + 2.0 * (1.0 - kb) * cb;
+        let g = (luma - kr * r - kb * b) / (1.0 - kr - kb);
+
+        [r, g, b]
+    }
+}
+
+/// Converts the decoded yuv frames into an rgba image keeping the bit depth
+fn to_image(
+    color: &Frame,
+    alpha: Option<&Frame>,
+    premultiplied: bool,
+) -> ThumbResult<DynamicImage> {
+    if let Some(alpha) = alpha {
+        if alpha.width != color.width || alpha.height != color.height {
+            return Err(ThumbError::Decode);
+        }
+    }
+    let max = if color.depth > 8 { 65535.0 } else { 255.0 };
+    let mut pixels = Vec::with_capacity(color.width * color.height * 4);
+
+    for y in 0..color.height {
+        for x in 0..color.width {
+            let [r, g, b] = color.rgb(x, y);
+            let a = alpha.map_or(1.0, |alpha| alpha.sample(0, x, y).clamp(0.0, 1.0));
+            let unpremultiply = if premultiplied && a > 0.0 { a } else { 1.0 };
+
+            for channel in [r / unpremultiply, g / unpremultiply, b / unpremultiply, a] {
+                pixels.push((channel.clamp(0.0, 1.0) * max).round() as u16);
+            }
+        }
+    }
+    let (width, height) = (color.width as u32, color.height as u32);
+
+    let image = if color.depth > 8 {
+        ImageBuffer::<Rgba<u16>, _>::from_raw(width, height, pixels).map(DynamicImage::ImageRgba16)
+    } else {
+        let pixels = pixels.into_iter().map(|value| value as u8).collect();
+        ImageBuffer::<Rgba<u8>, _>::from_raw(width, height, pixels).map(DynamicImage::ImageRgba8)
+    };
+
+    image.ok_or(ThumbError::Decode)
+}
+
+const fn subsampling(layout: Dav1dPixelLayout) -> (usize, usize) {
+    match layout {
+        DAV1D_PIXEL_LAYOUT_I420 => (1, 1),
+        DAV1D_PIXEL_LAYOUT_I422 => (1, 0),
+        _ => (0, 0),
+    }
+}
+
+/// A minimal wrapper around the dav1d compatible api of rav1d
+struct Decoder {
+    ctx: Option<Dav1dContext>,
+}
+
+impl Decoder {
+    fn new() -> ThumbResult<Self> {
+        let mut ctx = None;
+        // SAFETY: the settings are initialized by `dav1d_default_settings` before being read
+        // and `ctx` is only used after `dav1d_open` succeeded
+        let res = unsafe {
+            let mut settings = MaybeUninit::<Dav1dSettings>::uninit();
+            dav1d_default_settings(NonNull::from(&mut settings).cast());
+            let settings = settings.assume_init_mut();
+            // still images consist of a single frame, so there is nothing to pipeline
+            settings.max_frame_delay = 1;
+            settings.apply_grain = 1;
+
+            dav1d_open(Some(NonNull::from(&mut ctx)), Some(NonNull::from(settings)))
+        };
+        if res.0 != 0 {
+            return Err(ThumbError::Decode);
+        }
+
+        Ok(Self { ctx })
+    }
+
+    /// Decodes a single av1 item and copies the resulting planes
+    fn decode(&mut self, av1_data: &[u8]) -> ThumbResult<Frame> {
+        let ctx = self.ctx.ok_or(ThumbError::Decode)?;
+        let mut data = Dav1dData::default();
+
+        // SAFETY: the buffer returned by `dav1d_data_create` has the requested size and
+        // every picture is returned by `dav1d_get_picture` and unreferenced after its planes
+        // were copied
+        unsafe {
+            let buf = dav1d_data_create(Some(NonNull::from(&mut data)), av1_data.len());
+            if buf.is_null() {
+                return Err(ThumbError::Decode);
+            }
+            ptr::copy_nonoverlapping(av1_data.as_ptr(), buf, av1_data.len());
+            let res = dav1d_send_data(Some(ctx), Some(NonNull::from(&mut data)));
+            dav1d_data_unref(Some(NonNull::from(&mut data)));
+            if res.0 != 0 {
+                return Err(ThumbError::Decode);
+            }
+
+            // layered items produce one picture per layer where the last one is the complete image
+            let mut frame = None;
+            loop {
+                let mut picture = Dav1dPicture::default();
+                let res = dav1d_get_picture(Some(ctx), Some(NonNull::from(&mut picture)));
+                if res.0 != 0 {
+                    break;
+                }
+                frame = Some(copy_frame(&picture));
+                dav1d_picture_unref(Some(NonNull::from(&mut picture)));
+            }
+
+            frame.ok_or(ThumbError::Decode)?
+        }
+    }
+}
+
+impl Drop for Decoder {
+    fn drop(&mut self) {
+        // SAFETY: the context was created by `dav1d_open` and is not used afterwards
+        unsafe { dav1d_close(Some(NonNull::from(&mut self.ctx))) };
+    }
+}
+
+/// Copies the planes of a picture into owned buffers
+///
+/// # Safety
+///
+/// The picture must be a valid picture returned by `dav1d_get_picture`
+unsafe fn copy_frame(picture: &Dav1dPicture) -> ThumbResult<Frame> {
+    let width = usize::try_from(picture.p.w).map_err(|_| ThumbError::Decode)?;
+    let height = usize::try_from(picture.p.h).map_err(|_| ThumbError::Decode)?;
+    let depth = u32::try_from(picture.p.bpc).map_err(|_| ThumbError::Decode)?;
+    let layout = picture.p.layout;
+    let (ss_x, ss_y) = subsampling(layout);
+    let plane_count = if layout == DAV1D_PIXEL_LAYOUT_I400 {
+        1
+    } else {
+        3
+    };
+    let pixel_bytes = if depth > 8 { 2 } else { 1 };
+
+    let mut planes: [Vec<u16>; 3] = Default::default();
+    for (index, plane) in planes.iter_mut().enumerate().take(plane_count) {
+        let data = picture.data[index].ok_or(ThumbError::Decode)?;
+        let (plane_width, plane_height) = if index == 0 {
+            (width, height)
+        } else {
+            ((width + ss_x) >> ss_x, (height + ss_y) >> ss_y)
+        };
+        // luma and chroma planes have separate strides
+        let stride = usize::try_from(picture.stride[usize::from(index != 0)])
+            .map_err(|_| ThumbError::Decode)?
+            / pixel_bytes;
+        plane.reserve(plane_width * plane_height);
+
+        for row in 0..plane_height {
+            if pixel_bytes == 2 {
+                // SAFETY: the decoder allocates `stride` samples for every row of the plane
+                let samples = unsafe {
+                    std::slice::from_raw_parts(
+                        data.as_ptr().cast::<u16>().add(row * stride),
+                        plane_width,
+                    )
+                };
+                plane.extend_from_slice(samples);
+            } else {
+                // SAFETY: the decoder allocates `stride` samples for every row of the plane
+                let samples = unsafe {
+                    std::slice::from_raw_parts(
+                        data.as_ptr().cast::<u8>().add(row * stride),
+                        plane_width,
+                    )
+                };
+                plane.extend(samples.iter().map(|&sample| u16::from(sample)));
+            }
+        }
+    }
+    let (matrix, primaries, transfer, full_range) = match picture.seq_hdr {
+        Some(header) => {
+            // SAFETY: the sequence header is kept alive by the picture
+            let header = unsafe { header.as_ref() };
+            (
+                header.mtrx,
+                u8::try_from(header.pri).unwrap_or_default(),
+                u8::try_from(header.trc).unwrap_or_default(),
+                header.color_range != 0,
+            )
+        }
+        None => (DAV1D_MC_UNKNOWN, 0, 0, false),
+    };
+
+    Ok(Frame {
+        width,
+        height,
+        depth,
+        layout,
+        matrix,
+        primaries,
+        transfer,
+        full_range,
+        planes,
+    })
+}
@@ -0,0 +1,35 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::utils::ffmpeg_cli::{get_png_image, is_ffmpeg_installed};
+use crate::utils::heif_container::apply_transformations;
+use file_format::FileFormat;
+use image::{DynamicImage, ImageFormat, ImageReader};
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::PathBuf;
+
+/// Reads a heif based image (heic, avif) by letting ffmpeg decode it.
+/// Grid images are stitched together by ffmpeg 7.1 and newer.
+pub fn read_heif_image<R: Read>(mut reader: R, mime: FileFormat) -> ThumbResult<DynamicImage> {
+    lazy_static::lazy_static! { static ref FFMPEG_INSTALLED: bool = is_ffmpeg_installed(); }
+    if !*FFMPEG_INSTALLED {
+        return Err(ThumbError::Unsupported(mime));
+    }
+
+    let tempdir = tempfile::tempdir()?;
+    let path = PathBuf::from(tempdir.path())
+        .join("image")
+        .with_extension(mime.extension());
+
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+    fs::write(&path, &buf)?;
+
+    let png_bytes = get_png_image(
+        path.to_str()
+            .expect("path to tmpdir contains invalid characters"),
+    )?;
+    tempdir.close()?;
+    let img = ImageReader::with_format(Cursor::new(png_bytes), ImageFormat::Png).decode()?;
+
+    Ok(apply_transformations(img, &buf))
+}
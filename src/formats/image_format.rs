@@ -1,46 +1,302 @@
+use crate::color_profile::{
+    convert_cmyk_image, convert_color_profile, is_cmyk_profile, ColorProfile,
+};
 use crate::error::{ThumbError, ThumbResult};
+use crate::tone_mapping::{hdr_to_linear, HdrTransfer};
+use crate::utils::webp_container;
 use file_format::FileFormat;
-use image::io::Reader as ImageReader;
-use image::{DynamicImage, ImageFormat};
-use std::io::{BufRead, Read, Seek};
+use image::{DynamicImage, ImageDecoder, ImageError, ImageFormat, ImageReader};
+use std::io::{BufRead, Cursor, Read, Seek, SeekFrom};
 use webp::Decoder as WebpDecoder;
+use zune_jpeg::zune_core::bytestream::ZCursor;
+use zune_jpeg::zune_core::colorspace::ColorSpace;
+use zune_jpeg::zune_core::options::DecoderOptions;
+use zune_jpeg::JpegDecoder;
 
-/// Reads an image with a known mime type
-pub fn read_image<R: BufRead + Seek>(reader: R, format: FileFormat) -> ThumbResult<DynamicImage> {
+/// Reads an image with a known mime type and converts it from its embedded
+/// icc profile to the given color profile
+pub fn read_image<R: BufRead + Seek>(
+    reader: R,
+    format: FileFormat,
+    profile: &ColorProfile,
+) -> ThumbResult<DynamicImage> {
     match format {
-        FileFormat::Webp => read_webp_image(reader),
-        _ => read_generic_image(reader, mime_to_image_format(format)),
+        FileFormat::Webp => read_webp_image(reader, profile),
+        FileFormat::PortableNetworkGraphics => read_png_image(reader, profile),
+        FileFormat::JointPhotographicExpertsGroup => read_jpeg_image(reader, profile),
+        #[cfg(feature = "jxl")]
+        FileFormat::JpegXl => read_jxl_image(reader, profile),
+        _ => read_generic_image(reader, mime_to_image_format(format), profile).map_err(|e| {
+            match e {
+                // the format is known to image but its feature is disabled
+                ThumbError::Image(ImageError::Unsupported(_)) => ThumbError::Unsupported(format),
+                e => e,
+            }
+        }),
     }
-    /* match mime.essence_str() {
-        IMAGE_WEBP_MIME => read_webp_image(reader),
-        _ => read_generic_image(reader, mime_to_image_format(mime)),
-    }*/
 }
 
 /// Reads a webp image
-fn read_webp_image<R: Read>(mut reader: R) -> ThumbResult<DynamicImage> {
+fn read_webp_image<R: Read>(mut reader: R, profile: &ColorProfile) -> ThumbResult<DynamicImage> {
     let mut buf = Vec::new();
     reader.read_to_end(&mut buf)?;
     let webp_image = WebpDecoder::new(&buf)
         .decode()
         .ok_or_else(|| ThumbError::Decode)?;
+    let icc_profile = webp_container::read_icc_profile(&buf);
+
+    convert_color_profile(webp_image.to_image(), icc_profile.as_deref(), profile)
+}
+
+/// Reads a png image. Hdr pngs are converted to linear light instead of using their icc profile.
+fn read_png_image<R: BufRead + Seek>(
+    mut reader: R,
+    profile: &ColorProfile,
+) -> ThumbResult<DynamicImage> {
+    let start = reader.stream_position()?;
+    let transfer = png::Decoder::new(&mut reader)
+        .read_info()
+        .ok()
+        .and_then(|reader| png_hdr_transfer(reader.info()));
+    reader.seek(SeekFrom::Start(start))?;
+    let Some(transfer) = transfer else {
+        return read_generic_image(reader, Some(ImageFormat::Png), profile);
+    };
+    let image = ImageReader::with_format(reader, ImageFormat::Png).decode()?;
+
+    convert_color_profile(hdr_to_linear(image, Some(transfer)), None, profile)
+}
+
+/// Returns the hdr transfer function signalled by the cICP chunk together with the
+/// bt.2020 primaries, which are the only ones hdr images are converted from
+fn png_hdr_transfer(info: &png::Info) -> Option<HdrTransfer> {
+    const BT2020_PRIMARIES: u8 = 9;
+    const PQ_TRANSFER: u8 = 16;
+    const HLG_TRANSFER: u8 = 18;
 
-    Ok(webp_image.to_image())
+    let cicp = info.coding_independent_code_points?;
+    if cicp.color_primaries != BT2020_PRIMARIES {
+        return None;
+    }
+    match cicp.transfer_function {
+        PQ_TRANSFER => Some(HdrTransfer::Pq),
+        HLG_TRANSFER => Some(HdrTransfer::Hlg),
+        _ => None,
+    }
+}
+
+/// Reads a jpeg image. Cmyk jpegs with an embedded cmyk profile are converted
+/// with that profile instead of the naive conversion of the decoder.
+fn read_jpeg_image<R: Read>(mut reader: R, profile: &ColorProfile) -> ThumbResult<DynamicImage> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+
+    let mut decoder = JpegDecoder::new(ZCursor::new(&buf));
+    if decoder.decode_headers().is_ok()
+        && matches!(
+            decoder.input_colorspace(),
+            Some(ColorSpace::CMYK | ColorSpace::YCCK)
+        )
+    {
+        if let Some(icc_profile) = decoder.icc_profile().filter(|p| is_cmyk_profile(p)) {
+            return read_cmyk_jpeg_image(&buf, &icc_profile, profile);
+        }
+    }
+
+    read_generic_image(Cursor::new(buf), Some(ImageFormat::Jpeg), profile)
+}
+
+/// Decodes the raw ink values of a cmyk or ycck jpeg and converts them with the cmyk profile
+fn read_cmyk_jpeg_image(
+    buf: &[u8],
+    icc_profile: &[u8],
+    profile: &ColorProfile,
+) -> ThumbResult<DynamicImage> {
+    let mut decoder = JpegDecoder::new(ZCursor::new(buf));
+    decoder.decode_headers().map_err(|_| ThumbError::Decode)?;
+    let input_colorspace = decoder.input_colorspace().ok_or(ThumbError::Decode)?;
+    // decoding into the input colorspace returns the samples without any conversion
+    decoder.set_options(DecoderOptions::default().jpeg_set_out_colorspace(input_colorspace));
+    let mut samples = decoder.decode().map_err(|_| ThumbError::Decode)?;
+    let (width, height) = decoder.dimensions().ok_or(ThumbError::Decode)?;
+
+    if input_colorspace == ColorSpace::YCCK {
+        // the inverted cmy values are stored as ycc like in libjpeg
+        for pixel in samples.chunks_exact_mut(4) {
+            let rgb = ycbcr_to_rgb(pixel[0], pixel[1], pixel[2]);
+            pixel[..3].copy_from_slice(&rgb.map(|value| 255 - value));
+        }
+    }
+    // adobe applications store the ink values inverted
+    if has_adobe_marker(buf) {
+        samples
+            .iter_mut()
+            .for_each(|sample| *sample = 255 - *sample);
+    }
+    let image = convert_cmyk_image(width as u32, height as u32, &samples, icc_profile, profile)?;
+
+    Ok(DynamicImage::ImageRgb8(image))
+}
+
+/// Converts full range ycbcr as used by jfif to rgb
+fn ycbcr_to_rgb(y: u8, cb: u8, cr: u8) -> [u8; 3] {
+    let y = f32::from(y);
+    let cb = f32::from(cb) - 128.0;
+    let cr = f32::from(cr) - 128.0;
+
+    [
+        y + 1.402 * cr,
+        y - 0.344_136 * cb - 0.714_136 * cr,
+        y + 1.772 * cb,
+    ]
+    .map(|value| value.round().clamp(0.0, 255.0) as u8)
+}
+
+/// Checks the jpeg header for the APP14 segment written by adobe applications
+fn has_adobe_marker(buf: &[u8]) -> bool {
+    let mut offset = 2;
+
+    while offset + 4 <= buf.len() && buf[offset] == 0xFF {
+        let marker = buf[offset + 1];
+        // the image data follows the start of scan segment
+        if marker == 0xDA {
+            break;
+        }
+        let length = u16::from_be_bytes([buf[offset + 2], buf[offset + 3]]) as usize;
+        if marker == 0xEE && buf[offset + 4..].starts_with(b"Adobe") {
+            return true;
+        }
+        offset += 2 + length;
+    }
+
+    false
+}
+
+/// Reads a tga image. Tga files don't have a signature, so other files of unknown
+/// formats are only told apart by the plausibility of the header together with
+/// the footer of tga 2.0 files or the smallest size of the described image.
+#[cfg(feature = "tga")]
+pub fn read_tga_image<R: BufRead + Seek>(mut reader: R) -> ThumbResult<DynamicImage> {
+    let start = reader.stream_position()?;
+    let length = reader.seek(SeekFrom::End(0))? - start;
+    reader.seek(SeekFrom::Start(start))?;
+    let mut header = [0; 18];
+    let is_tga = reader.read_exact(&mut header).is_ok()
+        && is_tga_header(&header)
+        && (has_tga_footer(&mut reader, length)? || length >= min_tga_size(&header));
+    reader.seek(SeekFrom::Start(start))?;
+    if !is_tga {
+        return Err(ThumbError::Unsupported(FileFormat::ArbitraryBinaryData));
+    }
+
+    read_generic_image(reader, Some(ImageFormat::Tga), &ColorProfile::Srgb)
+}
+
+/// Checks for the signature at the end of the footer of tga 2.0 files
+#[cfg(feature = "tga")]
+fn has_tga_footer<R: Read + Seek>(reader: &mut R, length: u64) -> ThumbResult<bool> {
+    const SIGNATURE: &[u8; 18] = b"TRUEVISION-XFILE.\0";
+
+    // the footer follows the header and starts with two offsets
+    if length < 18 + 26 {
+        return Ok(false);
+    }
+    let mut signature = [0; 18];
+    reader.seek(SeekFrom::End(-18))?;
+    reader.read_exact(&mut signature)?;
+
+    Ok(&signature == SIGNATURE)
+}
+
+/// Returns the smallest size of a file with the image of the header. Uncompressed
+/// pixels are stored as they are and every run length packet covers at most 128 pixels.
+#[cfg(feature = "tga")]
+fn min_tga_size(header: &[u8; 18]) -> u64 {
+    let id_length = u64::from(header[0]);
+    let color_map_size = match header[1] {
+        1 => {
+            u64::from(u16::from_le_bytes([header[5], header[6]])) * u64::from(header[7]).div_ceil(8)
+        }
+        _ => 0,
+    };
+    let pixels = u64::from(u16::from_le_bytes([header[12], header[13]]))
+        * u64::from(u16::from_le_bytes([header[14], header[15]]));
+    let pixel_size = u64::from(header[16]).div_ceil(8);
+    let data_size = match header[2] {
+        9..=11 => pixels.div_ceil(128) * (1 + pixel_size),
+        _ => pixels * pixel_size,
+    };
+
+    header.len() as u64 + id_length + color_map_size + data_size
+}
+
+/// Checks the fields of the 18 byte tga header for values the decoder accepts
+#[cfg(feature = "tga")]
+fn is_tga_header(header: &[u8; 18]) -> bool {
+    let color_map_type = header[1];
+    let image_type = header[2];
+    let color_map_length = u16::from_le_bytes([header[5], header[6]]);
+    let color_map_depth = header[7];
+    let width = u16::from_le_bytes([header[12], header[13]]);
+    let height = u16::from_le_bytes([header[14], header[15]]);
+    let pixel_depth = header[16];
+    let alpha_bits = header[17] & 0x0F;
+    let is_color_mapped = matches!(image_type, 1 | 9);
+
+    let valid_color_map = match color_map_type {
+        0 => !is_color_mapped,
+        1 => color_map_length > 0 && matches!(color_map_depth, 15 | 16 | 24 | 32),
+        _ => false,
+    };
+
+    valid_color_map
+        && matches!(image_type, 1 | 2 | 3 | 9 | 10 | 11)
+        && width > 0
+        && height > 0
+        && matches!(pixel_depth, 8 | 15 | 16 | 24 | 32)
+        && alpha_bits <= pixel_depth
+        // the two reserved bits of the image descriptor are unset
+        && header[17] & 0xC0 == 0
+}
+
+/// Reads a jpeg xl image. Losslessly recompressed jpegs are decoded to their pixels as well.
+/// The pixels are rendered in the color encoding of the image, which is described by the
+/// icc profile the decoder reports whether it's embedded or signalled by the header.
+#[cfg(feature = "jxl")]
+fn read_jxl_image<R: Read>(reader: R, profile: &ColorProfile) -> ThumbResult<DynamicImage> {
+    let mut decoder = jxl_oxide::integration::JxlDecoder::new(reader)?;
+    let icc_profile = decoder.icc_profile().ok().flatten();
+    let image = DynamicImage::from_decoder(decoder)?;
+
+    convert_color_profile(image, icc_profile.as_deref(), profile)
 }
 
 /// Reads a generic image
 fn read_generic_image<R: BufRead + Seek>(
     reader: R,
     format: Option<ImageFormat>,
+    profile: &ColorProfile,
 ) -> ThumbResult<DynamicImage> {
+    // the tiff decoder can't read the icc profile anymore once limits are set
+    #[cfg(feature = "tiff")]
+    if format == Some(ImageFormat::Tiff) {
+        let mut decoder = image::codecs::tiff::TiffDecoder::new(reader)?;
+        let icc_profile = decoder.icc_profile().ok().flatten();
+        decoder.set_limits(image::Limits::default())?;
+        let image = DynamicImage::from_decoder(decoder)?;
+
+        return convert_color_profile(image, icc_profile.as_deref(), profile);
+    }
     let reader = if let Some(format) = format {
         ImageReader::with_format(reader, format)
     } else {
         ImageReader::new(reader).with_guessed_format()?
     };
-    let image = reader.decode()?;
+    let mut decoder = reader.into_decoder()?;
+    let icc_profile = decoder.icc_profile().ok().flatten();
+    let image = DynamicImage::from_decoder(decoder)?;
 
-    Ok(image)
+    convert_color_profile(image, icc_profile.as_deref(), profile)
 }
 
 #[allow(clippy::needless_pass_by_value)]
@@ -48,8 +304,26 @@ fn mime_to_image_format(mime: FileFormat) -> Option<ImageFormat> {
     match mime {
         FileFormat::PortableNetworkGraphics => Some(ImageFormat::Png),
         FileFormat::JointPhotographicExpertsGroup => Some(ImageFormat::Jpeg),
-        FileFormat::WindowsBitmap => Some(ImageFormat::Bmp),
         FileFormat::GraphicsInterchangeFormat => Some(ImageFormat::Gif),
+        #[cfg(feature = "tiff")]
+        FileFormat::TagImageFileFormat => Some(ImageFormat::Tiff),
+        #[cfg(feature = "bmp")]
+        FileFormat::WindowsBitmap => Some(ImageFormat::Bmp),
+        #[cfg(feature = "ico")]
+        FileFormat::WindowsIcon => Some(ImageFormat::Ico),
+        #[cfg(feature = "pnm")]
+        FileFormat::PortableBitmap
+        | FileFormat::PortableGraymap
+        | FileFormat::PortablePixmap
+        | FileFormat::PortableArbitraryMap => Some(ImageFormat::Pnm),
+        #[cfg(feature = "qoi")]
+        FileFormat::QuiteOkImage => Some(ImageFormat::Qoi),
+        #[cfg(feature = "dds")]
+        FileFormat::MicrosoftDirectdrawSurface => Some(ImageFormat::Dds),
+        #[cfg(feature = "hdr")]
+        FileFormat::RadianceHdr => Some(ImageFormat::Hdr),
+        #[cfg(feature = "openexr")]
+        FileFormat::Openexr => Some(ImageFormat::OpenExr),
         _ => None,
     }
 }
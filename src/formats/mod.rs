@@ -1,23 +1,195 @@
+use crate::color_profile::{convert_color_profile, ColorProfile};
 use crate::error::{ThumbError, ThumbResult};
 use crate::formats::image_format::read_image;
+use crate::formats::pdf_format::get_pdf_page;
+use crate::{resize_images, ThumbnailOptions, ThumbnailSize};
 use file_format::{FileFormat, Kind};
 use image::DynamicImage;
-use std::io::{BufRead, Seek};
+use std::io::{BufRead, Cursor, Seek};
 
 use crate::formats::video_format::get_video_frame;
 
+#[cfg(feature = "avif")]
+pub mod avif_format;
+pub mod heif_format;
 pub mod image_format;
+pub mod pdf_format;
+pub mod psd_format;
+pub mod raw_format;
+#[cfg(feature = "svg")]
+pub mod svg_format;
 pub mod video_format;
 
 /// Reads the buffer content into an image that can be used for thumbnail generation
-pub fn get_base_image<R: BufRead + Seek>(reader: R, mime: FileFormat) -> ThumbResult<DynamicImage> {
-    match mime.kind() {
-        Kind::Image => read_image(reader, mime),
+/// and converts it to the given color profile
+pub fn get_base_image<R: BufRead + Seek>(
+    reader: R,
+    mime: FileFormat,
+    profile: &ColorProfile,
+) -> ThumbResult<DynamicImage> {
+    let image = match mime.kind() {
+        #[cfg(feature = "svg")]
+        Kind::Image if mime == FileFormat::ScalableVectorGraphics => {
+            svg_format::read_svg_image(reader)
+        }
+        #[cfg(not(feature = "svg"))]
+        Kind::Image if mime == FileFormat::ScalableVectorGraphics => {
+            Err(ThumbError::Unsupported(mime))
+        }
+        #[cfg(feature = "avif")]
+        Kind::Image if mime == FileFormat::Av1ImageFileFormat => {
+            return avif_format::read_avif_image(reader, profile)
+        }
+        Kind::Image
+            if matches!(
+                mime,
+                FileFormat::Av1ImageFileFormat
+                    | FileFormat::HighEfficiencyImageCoding
+                    | FileFormat::HighEfficiencyImageFileFormat
+            ) =>
+        {
+            heif_format::read_heif_image(reader, mime)
+        }
+        Kind::Image if mime == FileFormat::TagImageFileFormat => {
+            return read_tiff_image(reader, profile)
+        }
+        Kind::Image
+            if matches!(
+                mime,
+                FileFormat::CanonRaw
+                    | FileFormat::CanonRaw2
+                    | FileFormat::CanonRaw3
+                    | FileFormat::NikonElectronicFile
+                    | FileFormat::OlympusRawFormat
+                    | FileFormat::PanasonicRaw
+                    | FileFormat::FujifilmRaw
+            ) =>
+        {
+            raw_format::read_raw_image(reader)
+        }
+        Kind::Image if mime == FileFormat::AdobePhotoshopDocument => {
+            return psd_format::read_psd_image(reader, profile)
+        }
+        Kind::Image => return read_image(reader, mime, profile),
         Kind::Video => get_video_frame(reader, mime),
+        Kind::Document => match mime {
+            FileFormat::PortableDocumentFormat => get_pdf_page(reader, 1, None),
+            _ => Err(ThumbError::Unsupported(mime)),
+        },
         Kind::Other => match mime {
             FileFormat::Mpeg4Part14 => get_video_frame(reader, mime),
+            #[cfg(feature = "tga")]
+            FileFormat::ArbitraryBinaryData => image_format::read_tga_image(reader),
             _ => Err(ThumbError::Unsupported(mime)),
         },
         _ => Err(ThumbError::Unsupported(mime)),
+    }?;
+
+    // all other decoders produce srgb images
+    convert_color_profile(image, None, profile)
+}
+
+/// Reads a tiff image. Camera raw files based on tiff (dng, arw, ...) are read
+/// from their embedded preview.
+fn read_tiff_image<R: BufRead + Seek>(
+    mut reader: R,
+    profile: &ColorProfile,
+) -> ThumbResult<DynamicImage> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+
+    if raw_format::is_raw_tiff(&buf) {
+        if let Ok(image) = raw_format::read_raw_preview(&buf) {
+            return convert_color_profile(image, None, profile);
+        }
+    }
+
+    read_image(Cursor::new(buf), FileFormat::TagImageFileFormat, profile)
+}
+
+/// Creates the images for all requested sizes. Vector formats are rendered at each size
+/// directly while all other formats are decoded once and resized afterwards.
+/// If requested, a preview that fits into `preview_size` with the aspect ratio of the source
+/// is created from the same decoded image.
+pub fn get_sized_images_with_preview<R: BufRead + Seek>(
+    reader: R,
+    mime: FileFormat,
+    sizes: &[ThumbnailSize],
+    options: &ThumbnailOptions,
+    preview_size: Option<u32>,
+) -> ThumbResult<(Vec<DynamicImage>, Option<DynamicImage>)> {
+    #[cfg(feature = "svg")]
+    if mime == FileFormat::ScalableVectorGraphics {
+        // svgs are rendered with their aspect ratio into the requested sizes
+        let mut sizes = sizes.to_vec();
+        sizes.extend(preview_size.map(|size| ThumbnailSize::Custom((size, size))));
+        let mut images = svg_format::render_svg_images(reader, &sizes)?
+            .into_iter()
+            .map(|image| convert_color_profile(image, None, &options.color_profile))
+            .collect::<ThumbResult<Vec<_>>>()?;
+        let preview = preview_size.and_then(|_| images.pop());
+
+        return Ok((images, preview));
     }
+    let image = if mime == FileFormat::PortableDocumentFormat {
+        get_pdf_page_image(reader, 1, sizes, options)?
+    } else {
+        get_base_image(reader, mime, &options.color_profile)?
+    };
+
+    Ok(resize_with_preview(image, sizes, options, preview_size))
+}
+
+/// Renders a pdf page at the resolution needed for the largest requested size
+/// and resizes it to all requested sizes
+pub fn get_pdf_page_images<R: BufRead + Seek>(
+    reader: R,
+    page: usize,
+    sizes: &[ThumbnailSize],
+    options: &ThumbnailOptions,
+) -> ThumbResult<Vec<DynamicImage>> {
+    let image = get_pdf_page_image(reader, page, sizes, options)?;
+
+    Ok(resize_images(&image, sizes, options))
+}
+
+fn get_pdf_page_image<R: BufRead + Seek>(
+    reader: R,
+    page: usize,
+    sizes: &[ThumbnailSize],
+    options: &ThumbnailOptions,
+) -> ThumbResult<DynamicImage> {
+    let scale_to = sizes
+        .iter()
+        .map(|size| {
+            let (width, height) = size.dimensions();
+            width.max(height)
+        })
+        .max()
+        // sizes with an unconstrained side need the page at its full resolution
+        .filter(|edge| *edge != u32::MAX);
+    let image = get_pdf_page(reader, page, scale_to)?;
+
+    convert_color_profile(image, None, &options.color_profile)
+}
+
+/// Resizes the image to all sizes and to a preview fitting into `preview_size` in one pass
+fn resize_with_preview(
+    image: DynamicImage,
+    sizes: &[ThumbnailSize],
+    options: &ThumbnailOptions,
+    preview_size: Option<u32>,
+) -> (Vec<DynamicImage>, Option<DynamicImage>) {
+    let mut sizes = sizes.to_vec();
+    if let Some(preview_size) = preview_size {
+        let (width, height) = (image.width(), image.height());
+        let scale = (preview_size as f32 / width.max(height) as f32).min(1.0);
+        let width = ((width as f32 * scale).round() as u32).max(1);
+        let height = ((height as f32 * scale).round() as u32).max(1);
+        sizes.push(ThumbnailSize::Custom((width, height)));
+    }
+    let mut images = resize_images(&image, &sizes, options);
+    let preview = preview_size.and_then(|_| images.pop());
+
+    (images, preview)
 }
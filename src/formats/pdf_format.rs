@@ -0,0 +1,62 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::utils::pdftoppm_cli::{get_png_page, is_pdftoppm_installed};
+use file_format::FileFormat;
+use image::{DynamicImage, ImageFormat, ImageReader};
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::PathBuf;
+
+const PDF_HEADER: &[u8] = b"%PDF-";
+
+/// Renders a single page of a pdf document. The page index starts at 1.
+/// If a scale is given, the longer side of the page is rendered with that many pixels.
+pub fn get_pdf_page<R: Read>(
+    mut reader: R,
+    page: usize,
+    scale_to: Option<u32>,
+) -> ThumbResult<DynamicImage> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+    validate_pdf(&buf)?;
+
+    lazy_static::lazy_static! { static ref PDFTOPPM_INSTALLED: bool = is_pdftoppm_installed(); }
+    if !*PDFTOPPM_INSTALLED {
+        return Err(ThumbError::Unsupported(FileFormat::PortableDocumentFormat));
+    }
+
+    let tempdir = tempfile::tempdir()?;
+    let path = PathBuf::from(tempdir.path())
+        .join("document")
+        .with_extension(FileFormat::PortableDocumentFormat.extension());
+    fs::write(&path, buf)?;
+
+    let png_bytes = get_png_page(
+        path.to_str()
+            .expect("path to tmpdir contains invalid characters"),
+        page,
+        scale_to,
+    )?;
+    tempdir.close()?;
+    let img = ImageReader::with_format(Cursor::new(png_bytes), ImageFormat::Png).decode()?;
+
+    Ok(img)
+}
+
+/// Checks the document for problems that can be detected without rendering it.
+/// Encrypted documents are left to pdftoppm as they can be opened without a password
+/// if only the permissions are protected by an owner password.
+fn validate_pdf(buf: &[u8]) -> ThumbResult<()> {
+    // the header may be preceded by arbitrary bytes within the first kilobyte
+    let head = &buf[..buf.len().min(1024)];
+    if !contains(head, PDF_HEADER) {
+        return Err(ThumbError::MalformedPdf);
+    }
+
+    Ok(())
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
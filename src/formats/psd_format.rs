@@ -0,0 +1,431 @@
+use crate::color_profile::{
+    convert_cmyk_image, convert_color_profile, is_cmyk_profile, ColorProfile,
+};
+use crate::error::{ThumbError, ThumbResult};
+use image::{
+    DynamicImage, ImageBuffer, ImageFormat, ImageReader, Luma, LumaA, Rgb, Rgba, RgbaImage,
+};
+use std::io::{Cursor, Read};
+
+const SIGNATURE: &[u8] = b"8BPS";
+const RESOURCE_SIGNATURE: &[u8] = b"8BIM";
+const VERSION_INFO_RESOURCE: u16 = 1057;
+const THUMBNAIL_RESOURCE: u16 = 1036;
+/// Thumbnail resource of Photoshop 4 which stores the channels as bgr
+const BGR_THUMBNAIL_RESOURCE: u16 = 1033;
+const THUMBNAIL_HEADER_SIZE: usize = 28;
+const ICC_PROFILE_RESOURCE: u16 = 1039;
+
+const COLOR_MODE_GRAYSCALE: u16 = 1;
+const COLOR_MODE_RGB: u16 = 3;
+const COLOR_MODE_CMYK: u16 = 4;
+const COLOR_MODE_DUOTONE: u16 = 8;
+
+const COMPRESSION_RAW: u16 = 0;
+const COMPRESSION_RLE: u16 = 1;
+
+/// Reads the merged composite image of a photoshop document and converts it from the
+/// embedded icc profile to the target profile.
+/// If the document doesn't contain a composite, the embedded thumbnail is used instead.
+pub fn read_psd_image<R: Read>(mut reader: R, profile: &ColorProfile) -> ThumbResult<DynamicImage> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+    let document = Document::parse(&buf).ok_or(ThumbError::Decode)?;
+
+    if document.has_composite {
+        if let Some(image) = document.composite(profile) {
+            return image;
+        }
+    }
+
+    convert_color_profile(document.thumbnail()?, None, profile)
+}
+
+struct Header {
+    /// psb files use 64 bit lengths in some places
+    large: bool,
+    channels: usize,
+    height: usize,
+    width: usize,
+    depth: u16,
+    color_mode: u16,
+}
+
+struct Document<'a> {
+    header: Header,
+    has_composite: bool,
+    has_transparency: bool,
+    thumbnail: Option<(u16, &'a [u8])>,
+    icc_profile: Option<&'a [u8]>,
+    image_data: &'a [u8],
+}
+
+impl<'a> Document<'a> {
+    fn parse(buf: &'a [u8]) -> Option<Self> {
+        let mut reader = ByteReader::new(buf);
+        if reader.take(4)? != SIGNATURE {
+            return None;
+        }
+        let version = reader.u16()?;
+        reader.take(6)?;
+        let header = Header {
+            large: version == 2,
+            channels: usize::from(reader.u16()?),
+            height: reader.u32()? as usize,
+            width: reader.u32()? as usize,
+            depth: reader.u16()?,
+            color_mode: reader.u16()?,
+        };
+
+        let color_mode_data_length = reader.u32()? as usize;
+        reader.take(color_mode_data_length)?;
+
+        let resources_length = reader.u32()? as usize;
+        let resources = parse_resources(reader.take(resources_length)?);
+
+        let layers_length = reader.length(header.large)?;
+        let has_transparency = parse_transparency(reader.take(layers_length)?, header.large);
+
+        Some(Self {
+            header,
+            has_composite: resources.has_composite,
+            has_transparency,
+            thumbnail: resources.thumbnail,
+            icc_profile: resources.icc_profile,
+            image_data: reader.rest(),
+        })
+    }
+
+    /// Decodes the composite image stored in the image data section. Returns `None` if the
+    /// composite can't be decoded and the error of the color conversion otherwise.
+    fn composite(&self, profile: &ColorProfile) -> Option<ThumbResult<DynamicImage>> {
+        let header = &self.header;
+        let color_channels = match header.color_mode {
+            COLOR_MODE_GRAYSCALE | COLOR_MODE_DUOTONE => 1,
+            COLOR_MODE_RGB => 3,
+            COLOR_MODE_CMYK => 4,
+            _ => return None,
+        };
+        if !matches!(header.depth, 8 | 16) || header.channels < color_channels {
+            return None;
+        }
+        // the first extra channel holds the transparency of the merged layers
+        let channels = if self.has_transparency && header.channels > color_channels {
+            color_channels + 1
+        } else {
+            color_channels
+        };
+        let mut planes = (0..channels)
+            .map(|channel| self.plane(channel))
+            .collect::<Option<Vec<_>>>()?;
+
+        let max = if header.depth == 16 { 65535 } else { 255 };
+        if channels > color_channels {
+            unmatte(&mut planes, max);
+        }
+        if header.color_mode == COLOR_MODE_CMYK {
+            if let Some(icc_profile) = self.icc_profile.filter(|icc| is_cmyk_profile(icc)) {
+                return Some(self.convert_cmyk(&planes, icc_profile, profile));
+            }
+        }
+
+        let pixel_count = header.width * header.height;
+        let mut pixels = Vec::with_capacity(pixel_count * channels);
+        for i in 0..pixel_count {
+            if header.color_mode == COLOR_MODE_CMYK {
+                // cmyk values are stored inverted, so 0 means full ink
+                let black = u32::from(planes[3][i]);
+                for plane in &planes[..3] {
+                    pixels.push((u32::from(plane[i]) * black / max) as u16);
+                }
+                if channels > 4 {
+                    pixels.push(planes[4][i]);
+                }
+            } else {
+                pixels.extend(planes.iter().map(|plane| plane[i]));
+            }
+        }
+
+        // cmyk is converted to rgb
+        let output_channels = if header.color_mode == COLOR_MODE_CMYK {
+            channels - 1
+        } else {
+            channels
+        };
+
+        let image = to_image(header, output_channels, pixels)?;
+        // the naive cmyk conversion already produces srgb
+        let icc_profile = self
+            .icc_profile
+            .filter(|_| header.color_mode != COLOR_MODE_CMYK);
+
+        Some(convert_color_profile(image, icc_profile, profile))
+    }
+
+    /// Converts the cmyk planes with the embedded cmyk profile and keeps the transparency
+    fn convert_cmyk(
+        &self,
+        planes: &[Vec<u16>],
+        icc_profile: &[u8],
+        profile: &ColorProfile,
+    ) -> ThumbResult<DynamicImage> {
+        let header = &self.header;
+        let (width, height) = (header.width as u32, header.height as u32);
+        // the samples are reduced to 8 bits and inverted so 0 means no ink
+        let shift = header.depth - 8;
+        let to_u8 = |value: u16| (value >> shift) as u8;
+        let cmyk: Vec<u8> = (0..header.width * header.height)
+            .flat_map(|i| planes[..4].iter().map(move |plane| 255 - to_u8(plane[i])))
+            .collect();
+        let rgb = convert_cmyk_image(width, height, &cmyk, icc_profile, profile)?;
+
+        let Some(alpha) = planes.get(4) else {
+            return Ok(DynamicImage::ImageRgb8(rgb));
+        };
+        let rgba = RgbaImage::from_fn(width, height, |x, y| {
+            let [r, g, b] = rgb.get_pixel(x, y).0;
+            let a = to_u8(alpha[(y * width + x) as usize]);
+            Rgba([r, g, b, a])
+        });
+
+        Ok(DynamicImage::ImageRgba8(rgba))
+    }
+
+    /// Decodes a single channel of the image data section into samples
+    fn plane(&self, channel: usize) -> Option<Vec<u16>> {
+        let header = &self.header;
+        let mut reader = ByteReader::new(self.image_data);
+        let bytes_per_sample = usize::from(header.depth / 8);
+        let row_length = header.width * bytes_per_sample;
+
+        let data = match reader.u16()? {
+            COMPRESSION_RAW => {
+                let plane_length = row_length * header.height;
+                reader.take(plane_length * channel)?;
+                reader.take(plane_length)?.to_vec()
+            }
+            COMPRESSION_RLE => {
+                let rows = header.channels * header.height;
+                let counts = (0..rows)
+                    .map(|_| {
+                        if header.large {
+                            reader.u32().map(|c| c as usize)
+                        } else {
+                            reader.u16().map(usize::from)
+                        }
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                let skipped: usize = counts[..channel * header.height].iter().sum();
+                reader.take(skipped)?;
+
+                let mut data = Vec::with_capacity(row_length * header.height);
+                for &count in &counts[channel * header.height..(channel + 1) * header.height] {
+                    let row = unpack_bits(reader.take(count)?, row_length)?;
+                    data.extend_from_slice(&row);
+                }
+                data
+            }
+            _ => return None,
+        };
+
+        let samples = if bytes_per_sample == 2 {
+            data.chunks_exact(2)
+                .map(|s| u16::from_be_bytes([s[0], s[1]]))
+                .collect()
+        } else {
+            data.into_iter().map(u16::from).collect()
+        };
+
+        Some(samples)
+    }
+
+    /// Decodes the jpeg thumbnail stored in the image resources
+    fn thumbnail(&self) -> ThumbResult<DynamicImage> {
+        let (id, data) = self.thumbnail.ok_or(ThumbError::Decode)?;
+        let jpeg = data
+            .get(THUMBNAIL_HEADER_SIZE..)
+            .ok_or(ThumbError::Decode)?;
+        let image = ImageReader::with_format(Cursor::new(jpeg), ImageFormat::Jpeg).decode()?;
+
+        if id == BGR_THUMBNAIL_RESOURCE {
+            let mut image = image.into_rgb8();
+            image.pixels_mut().for_each(|pixel| pixel.0.swap(0, 2));
+            return Ok(DynamicImage::ImageRgb8(image));
+        }
+
+        Ok(image)
+    }
+}
+
+struct Resources<'a> {
+    /// whether the document contains a real composite
+    has_composite: bool,
+    /// the id and data of the best thumbnail resource
+    thumbnail: Option<(u16, &'a [u8])>,
+    icc_profile: Option<&'a [u8]>,
+}
+
+fn parse_resources(buf: &[u8]) -> Resources<'_> {
+    let mut reader = ByteReader::new(buf);
+    let mut resources = Resources {
+        has_composite: true,
+        thumbnail: None,
+        icc_profile: None,
+    };
+
+    while reader.take(4) == Some(RESOURCE_SIGNATURE) {
+        let Some(id) = reader.u16() else { break };
+        // the name is a pascal string padded to an even length
+        let Some(name_length) = reader.u8() else {
+            break;
+        };
+        let name_padding = usize::from(name_length % 2 == 0);
+        if reader
+            .take(usize::from(name_length) + name_padding)
+            .is_none()
+        {
+            break;
+        }
+        let Some(length) = reader.u32().map(|l| l as usize) else {
+            break;
+        };
+        let Some(data) = reader.take(length) else {
+            break;
+        };
+        reader.take(length % 2);
+
+        match id {
+            VERSION_INFO_RESOURCE => {
+                // the version is followed by the `hasRealMergedData` flag
+                resources.has_composite = data.get(4) != Some(&0);
+            }
+            THUMBNAIL_RESOURCE => resources.thumbnail = Some((id, data)),
+            BGR_THUMBNAIL_RESOURCE if resources.thumbnail.is_none() => {
+                resources.thumbnail = Some((id, data));
+            }
+            ICC_PROFILE_RESOURCE => resources.icc_profile = Some(data),
+            _ => {}
+        }
+    }
+
+    resources
+}
+
+/// A negative layer count means that the composite has a transparency channel
+fn parse_transparency(buf: &[u8], large: bool) -> bool {
+    let mut reader = ByteReader::new(buf);
+    reader
+        .length(large)
+        .and_then(|_| reader.u16())
+        .is_some_and(|count| (count as i16) < 0)
+}
+
+/// The composite is matted against white, so the colors of partially transparent pixels
+/// are restored with `c' = (c - (1 - a)) / a`. The alpha is stored in the last plane.
+fn unmatte(planes: &mut [Vec<u16>], max: u32) {
+    let Some((alpha, colors)) = planes.split_last_mut() else {
+        return;
+    };
+
+    for (i, &a) in alpha.iter().enumerate() {
+        let a = u32::from(a);
+        if a == 0 || a == max {
+            continue;
+        }
+        for plane in colors.iter_mut() {
+            let color = u32::from(plane[i]).saturating_sub(max - a);
+            plane[i] = ((color * max + a / 2) / a).min(max) as u16;
+        }
+    }
+}
+
+/// Decompresses a packbits encoded row
+fn unpack_bits(mut data: &[u8], length: usize) -> Option<Vec<u8>> {
+    let mut row = Vec::with_capacity(length);
+
+    while let Some((&header, rest)) = data.split_first() {
+        let header = header as i8;
+        if header >= 0 {
+            let count = header as usize + 1;
+            row.extend_from_slice(rest.get(..count)?);
+            data = &rest[count..];
+        } else if header != -128 {
+            let count = 1 - header as isize;
+            row.extend(std::iter::repeat_n(*rest.first()?, count as usize));
+            data = &rest[1..];
+        } else {
+            data = rest;
+        }
+    }
+
+    (row.len() == length).then_some(row)
+}
+
+fn to_image(header: &Header, channels: usize, pixels: Vec<u16>) -> Option<DynamicImage> {
+    let (width, height) = (header.width as u32, header.height as u32);
+
+    if header.depth == 16 {
+        match channels {
+            1 => ImageBuffer::<Luma<u16>, _>::from_raw(width, height, pixels).map(Into::into),
+            2 => ImageBuffer::<LumaA<u16>, _>::from_raw(width, height, pixels).map(Into::into),
+            3 => ImageBuffer::<Rgb<u16>, _>::from_raw(width, height, pixels).map(Into::into),
+            _ => ImageBuffer::<Rgba<u16>, _>::from_raw(width, height, pixels).map(Into::into),
+        }
+    } else {
+        let pixels: Vec<u8> = pixels.into_iter().map(|value| value as u8).collect();
+        match channels {
+            1 => ImageBuffer::<Luma<u8>, _>::from_raw(width, height, pixels).map(Into::into),
+            2 => ImageBuffer::<LumaA<u8>, _>::from_raw(width, height, pixels).map(Into::into),
+            3 => ImageBuffer::<Rgb<u8>, _>::from_raw(width, height, pixels).map(Into::into),
+            _ => ImageBuffer::<Rgba<u8>, _>::from_raw(width, height, pixels).map(Into::into),
+        }
+    }
+}
+
+/// Reads big endian values from a buffer
+struct ByteReader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    const fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    fn take(&mut self, length: usize) -> Option<&'a [u8]> {
+        if length > self.buf.len() {
+            return None;
+        }
+        let (head, tail) = self.buf.split_at(length);
+        self.buf = tail;
+
+        Some(head)
+    }
+
+    const fn rest(&self) -> &'a [u8] {
+        self.buf
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// Reads a section length which is 64 bit in psb files
+    fn length(&mut self, large: bool) -> Option<usize> {
+        if large {
+            self.take(8)
+                .and_then(|b| usize::try_from(u64::from_be_bytes(b.try_into().ok()?)).ok())
+        } else {
+            self.u32().map(|length| length as usize)
+        }
+    }
+}
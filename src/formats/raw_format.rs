@@ -0,0 +1,556 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::utils::exif::read_orientation;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageFormat, ImageReader};
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::ops::Range;
+
+const NEW_SUBFILE_TYPE_TAG: u16 = 0x00FE;
+const COMPRESSION_TAG: u16 = 0x0103;
+const PHOTOMETRIC_INTERPRETATION_TAG: u16 = 0x0106;
+const STRIP_OFFSETS_TAG: u16 = 0x0111;
+const ORIENTATION_TAG: u16 = 0x0112;
+const STRIP_BYTE_COUNTS_TAG: u16 = 0x0117;
+const SUB_IFDS_TAG: u16 = 0x014A;
+const JPEG_OFFSET_TAG: u16 = 0x0201;
+const JPEG_LENGTH_TAG: u16 = 0x0202;
+const DNG_VERSION_TAG: u16 = 0xC612;
+
+/// The photometric interpretations of sensor data (color filter array and linear raw)
+const SENSOR_PHOTOMETRIC_INTERPRETATIONS: [u32; 2] = [32803, 34892];
+/// The compressions camera vendors use for their sensor data
+/// (sony arw, samsung srw, panasonic rw2, nikon nef and kodak dcr)
+const VENDOR_RAW_COMPRESSIONS: [u32; 5] = [32767, 32770, 34316, 34713, 65000];
+/// Guards against cyclic or excessively nested directories
+const MAX_IFDS: usize = 64;
+
+const FUJIFILM_MAGIC: &[u8] = b"FUJIFILMCCD-RAW ";
+/// The uuid of the canon box containing the preview of cr3 files
+const CANON_PREVIEW_UUID: [u8; 16] = [
+    0xEA, 0xF4, 0x2B, 0x5E, 0x1C, 0x98, 0x4B, 0x88, 0xB9, 0xFB, 0xB7, 0xDC, 0x40, 0x6E, 0x4D, 0x16,
+];
+/// The uuid of the canon box inside of `moov` containing the metadata of cr3 files
+const CANON_METADATA_UUID: [u8; 16] = [
+    0x85, 0xC0, 0xB6, 0x87, 0x82, 0x0F, 0x11, 0xE0, 0x81, 0x11, 0xF4, 0xCE, 0x46, 0x2B, 0x6A, 0x48,
+];
+
+/// The type of the heap of canon crw files after the byte order and the header length
+const CIFF_MAGIC: &[u8] = b"HEAPCCDR";
+const CIFF_JPEG_TAG: u16 = 0x2007;
+const CIFF_IMAGE_INFO_TAG: u16 = 0x1810;
+
+/// A jpeg stream embedded in a raw file
+struct Preview {
+    range: Range<usize>,
+    width: u16,
+    height: u16,
+}
+
+impl Preview {
+    fn area(&self) -> u32 {
+        u32::from(self.width) * u32::from(self.height)
+    }
+}
+
+/// A tiff based container. Besides tiff itself this includes the
+/// olympus (orf) and panasonic (rw2) variants with their own magic numbers.
+struct Tiff<'a> {
+    buf: &'a [u8],
+    big_endian: bool,
+}
+
+/// The integer values of the entries of an image file directory
+struct Ifd {
+    entries: HashMap<u16, Vec<u32>>,
+}
+
+/// Returns whether the tiff data is a camera raw (dng, nef, cr2, arw, ...) rather than a
+/// regular tiff. Only dng versions, sensor data and the header of cr2 files are raw specific,
+/// other tags like the camera make are written into regular tiffs as well.
+pub fn is_raw_tiff(buf: &[u8]) -> bool {
+    let Some(tiff) = Tiff::new(buf) else {
+        return false;
+    };
+
+    tiff.is_cr2()
+        || tiff.ifds().iter().any(|ifd| {
+            ifd.entries.contains_key(&DNG_VERSION_TAG)
+                || ifd
+                    .value(PHOTOMETRIC_INTERPRETATION_TAG)
+                    .is_some_and(|value| SENSOR_PHOTOMETRIC_INTERPRETATIONS.contains(&value))
+                || ifd
+                    .value(COMPRESSION_TAG)
+                    .is_some_and(|value| VENDOR_RAW_COMPRESSIONS.contains(&value))
+        })
+}
+
+/// Reads a camera raw file
+pub fn read_raw_image<R: Read>(mut reader: R) -> ThumbResult<DynamicImage> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+
+    read_raw_preview(&buf)
+}
+
+/// Reads the largest jpeg preview embedded in a camera raw file. The previews are looked up
+/// in the image file directories of tiff based containers (cr2, nef, arw, dng, orf, rw2), the
+/// header of fujifilm raf files, the heap of crw files and the preview box of cr3 files.
+pub fn read_raw_preview(buf: &[u8]) -> ThumbResult<DynamicImage> {
+    let (mut previews, raw_orientation) = match Tiff::new(buf) {
+        Some(tiff) => (tiff.previews(), tiff.orientation()),
+        None if buf.starts_with(FUJIFILM_MAGIC) => (raf_preview(buf).into_iter().collect(), None),
+        None if buf.get(6..14) == Some(CIFF_MAGIC) => {
+            let heap = Ciff::root(buf);
+            let previews = heap.as_ref().and_then(Ciff::preview);
+            (
+                previews.into_iter().collect(),
+                heap.and_then(|heap| heap.orientation()),
+            )
+        }
+        None => (cr3_preview(buf).into_iter().collect(), cr3_orientation(buf)),
+    };
+    previews.sort_by_key(|preview| std::cmp::Reverse(preview.area()));
+
+    for preview in previews {
+        let bytes = &buf[preview.range];
+        let Ok(mut image) =
+            ImageReader::with_format(Cursor::new(bytes), ImageFormat::Jpeg).decode()
+        else {
+            continue;
+        };
+        // the orientation of the raw file takes precedence over the one of the preview
+        let orientation = raw_orientation.or_else(|| read_orientation(bytes));
+        if let Some(orientation) = orientation {
+            image.apply_orientation(orientation);
+        }
+
+        return Ok(image);
+    }
+
+    Err(ThumbError::Decode)
+}
+
+impl<'a> Tiff<'a> {
+    fn new(buf: &'a [u8]) -> Option<Self> {
+        let big_endian = match buf.get(..2)? {
+            b"II" => false,
+            b"MM" => true,
+            _ => return None,
+        };
+        let tiff = Self { buf, big_endian };
+
+        // tiff, olympus and panasonic
+        matches!(tiff.u16(2)?, 42 | 0x4F52 | 0x5352 | 0x55).then_some(tiff)
+    }
+
+    /// Canon marks cr2 files with `CR` and the major version after the tiff header
+    fn is_cr2(&self) -> bool {
+        self.buf.get(8..10) == Some(b"CR")
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes = self.buf.get(offset..offset + 2)?.try_into().ok()?;
+
+        Some(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let bytes = self.buf.get(offset..offset + 4)?.try_into().ok()?;
+
+        Some(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    /// Returns the chain of directories starting at the header together with their sub ifds
+    fn ifds(&self) -> Vec<Ifd> {
+        let mut ifds = Vec::new();
+        let mut visited = Vec::new();
+        let mut pending = self.u32(4).into_iter().collect::<Vec<_>>();
+
+        while let Some(offset) = pending.pop() {
+            if offset == 0 || visited.contains(&offset) || visited.len() == MAX_IFDS {
+                continue;
+            }
+            visited.push(offset);
+            let Some((ifd, next)) = self.read_ifd(offset as usize) else {
+                continue;
+            };
+            pending.push(next);
+            if let Some(sub_ifds) = ifd.entries.get(&SUB_IFDS_TAG) {
+                pending.extend(sub_ifds.iter().rev());
+            }
+            ifds.push(ifd);
+        }
+
+        ifds
+    }
+
+    /// Reads the directory at the offset and returns it with the offset of the next one
+    fn read_ifd(&self, offset: usize) -> Option<(Ifd, u32)> {
+        let count = usize::from(self.u16(offset)?);
+        let mut entries = HashMap::new();
+
+        for index in 0..count {
+            let entry = offset + 2 + index * 12;
+            let tag = self.u16(entry)?;
+            let value_type = self.u16(entry + 2)?;
+            let value_count = self.u32(entry + 4)? as usize;
+            // bytes, shorts, longs and ifds, other types aren't needed to find the previews
+            let size = match value_type {
+                1 | 7 => 1,
+                3 => 2,
+                4 | 13 => 4,
+                _ => continue,
+            };
+            let values_offset = if value_count.saturating_mul(size) <= 4 {
+                entry + 8
+            } else {
+                self.u32(entry + 8)? as usize
+            };
+            let values = (0..value_count.min(MAX_IFDS))
+                .map_while(|index| {
+                    let offset = values_offset + index * size;
+                    match size {
+                        1 => self.buf.get(offset).copied().map(u32::from),
+                        2 => self.u16(offset).map(u32::from),
+                        _ => self.u32(offset),
+                    }
+                })
+                .collect();
+            entries.insert(tag, values);
+        }
+        let next = self.u32(offset + 2 + count * 12).unwrap_or(0);
+
+        Some((Ifd { entries }, next))
+    }
+
+    /// Returns the orientation of the first directory
+    fn orientation(&self) -> Option<Orientation> {
+        let ifd = self.ifds().into_iter().next()?;
+        let value = ifd.value(ORIENTATION_TAG)?;
+
+        Orientation::from_exif(u8::try_from(value).ok()?)
+    }
+
+    /// Returns the jpeg streams referenced by the directories. These are the jpeg
+    /// thumbnails of the exif data and the images stored in a single strip that
+    /// are either reduced resolution images or don't contain sensor data.
+    fn previews(&self) -> Vec<Preview> {
+        let mut previews = Vec::new();
+
+        for ifd in self.ifds() {
+            if let (Some(offset), Some(length)) =
+                (ifd.value(JPEG_OFFSET_TAG), ifd.value(JPEG_LENGTH_TAG))
+            {
+                previews.extend(parse_jpeg_at(self.buf, offset, length));
+            }
+            let is_reduced_resolution = ifd
+                .value(NEW_SUBFILE_TYPE_TAG)
+                .is_some_and(|value| value & 1 == 1);
+            let is_sensor_data = ifd
+                .value(PHOTOMETRIC_INTERPRETATION_TAG)
+                .is_some_and(|value| SENSOR_PHOTOMETRIC_INTERPRETATIONS.contains(&value));
+            if !is_reduced_resolution && is_sensor_data {
+                continue;
+            }
+            if let (Some([offset]), Some([length])) = (
+                ifd.entries.get(&STRIP_OFFSETS_TAG).map(Vec::as_slice),
+                ifd.entries.get(&STRIP_BYTE_COUNTS_TAG).map(Vec::as_slice),
+            ) {
+                previews.extend(parse_jpeg_at(self.buf, *offset, *length));
+            }
+        }
+
+        previews
+    }
+}
+
+impl Ifd {
+    fn value(&self, tag: u16) -> Option<u32> {
+        self.entries.get(&tag)?.first().copied()
+    }
+}
+
+/// Returns the preview of a fujifilm raf file whose header stores the
+/// big endian offset and length of the jpeg at byte 84
+fn raf_preview(buf: &[u8]) -> Option<Preview> {
+    let offset = u32::from_be_bytes(buf.get(84..88)?.try_into().ok()?);
+    let length = u32::from_be_bytes(buf.get(88..92)?.try_into().ok()?);
+
+    parse_jpeg_at(buf, offset, length)
+}
+
+/// Returns the preview of a cr3 file which is stored in a `PRVW` box inside of a top level
+/// uuid box. The jpeg follows the dimensions and the length after 24 bytes of the box.
+fn cr3_preview(buf: &[u8]) -> Option<Preview> {
+    let boxes = iso_boxes(buf, 0..buf.len());
+    let (_, range) = boxes.into_iter().find(|(kind, range)| {
+        kind == b"uuid" && buf.get(range.start..range.start + 16) == Some(&CANON_PREVIEW_UUID)
+    })?;
+    // the uuid is followed by 8 bytes before the boxes start
+    let (_, range) = iso_boxes(buf, range.start + 24..range.end)
+        .into_iter()
+        .find(|(kind, _)| kind == b"PRVW")?;
+    let length = u32::from_be_bytes(
+        buf.get(range.start + 12..range.start + 16)?
+            .try_into()
+            .ok()?,
+    );
+
+    parse_jpeg_at(buf, u32::try_from(range.start + 16).ok()?, length)
+}
+
+/// Returns the orientation of a cr3 file which is stored in the tiff of the `CMT1` box
+/// inside of the canon uuid box of `moov`
+fn cr3_orientation(buf: &[u8]) -> Option<Orientation> {
+    let (_, moov) = iso_boxes(buf, 0..buf.len())
+        .into_iter()
+        .find(|(kind, _)| kind == b"moov")?;
+    let (_, range) = iso_boxes(buf, moov).into_iter().find(|(kind, range)| {
+        kind == b"uuid" && buf.get(range.start..range.start + 16) == Some(&CANON_METADATA_UUID)
+    })?;
+    let (_, range) = iso_boxes(buf, range.start + 16..range.end)
+        .into_iter()
+        .find(|(kind, _)| kind == b"CMT1")?;
+
+    Tiff::new(&buf[range])?.orientation()
+}
+
+/// A heap of a canon crw file. The heap ends with the offset of its record table, which lists
+/// the tag, length and offset of each record relative to the start of the heap.
+struct Ciff<'a> {
+    buf: &'a [u8],
+    big_endian: bool,
+    range: Range<usize>,
+    depth: usize,
+}
+
+impl<'a> Ciff<'a> {
+    /// Returns the root heap that follows the header
+    fn root(buf: &'a [u8]) -> Option<Self> {
+        let big_endian = match buf.get(..2)? {
+            b"II" => false,
+            b"MM" => true,
+            _ => return None,
+        };
+        let mut heap = Self {
+            buf,
+            big_endian,
+            range: 0..buf.len(),
+            depth: 0,
+        };
+        heap.range = heap.u32(2)? as usize..buf.len();
+
+        Some(heap)
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        Tiff {
+            buf: self.buf,
+            big_endian: self.big_endian,
+        }
+        .u16(offset)
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        Tiff {
+            buf: self.buf,
+            big_endian: self.big_endian,
+        }
+        .u32(offset)
+    }
+
+    /// Returns the records of the heap and its sub heaps as tag id and data
+    fn records(&self) -> Vec<(u16, Range<usize>)> {
+        let mut records = Vec::new();
+        if self.depth == MAX_IFDS || self.range.len() < 4 {
+            return records;
+        }
+        let Some(table) = self
+            .u32(self.range.end - 4)
+            .and_then(|offset| self.range.start.checked_add(offset as usize))
+        else {
+            return records;
+        };
+        let count = self.u16(table).map_or(0, usize::from);
+
+        for index in 0..count {
+            let entry = table + 2 + index * 10;
+            let (Some(tag), Some(length), Some(offset)) =
+                (self.u16(entry), self.u32(entry + 2), self.u32(entry + 6))
+            else {
+                break;
+            };
+            // records stored in the table itself are too small to be needed
+            if tag & 0xC000 != 0 {
+                continue;
+            }
+            let start = self.range.start.saturating_add(offset as usize);
+            let end = start.saturating_add(length as usize);
+            if end > self.range.end {
+                continue;
+            }
+            // the data type of sub heaps is 0x2800 or 0x3000
+            if matches!(tag & 0x3800, 0x2800 | 0x3000) {
+                let heap = Self {
+                    range: start..end,
+                    depth: self.depth + 1,
+                    ..*self
+                };
+                records.extend(heap.records());
+            } else {
+                records.push((tag & 0x3FFF, start..end));
+            }
+        }
+
+        records
+    }
+
+    fn preview(&self) -> Option<Preview> {
+        let (_, range) = self
+            .records()
+            .into_iter()
+            .find(|(tag, _)| *tag == CIFF_JPEG_TAG)?;
+
+        parse_jpeg_at(
+            self.buf,
+            u32::try_from(range.start).ok()?,
+            u32::try_from(range.len()).ok()?,
+        )
+    }
+
+    /// Returns the orientation of the image info record, which follows the width,
+    /// height and pixel aspect ratio as the clockwise rotation in degrees
+    fn orientation(&self) -> Option<Orientation> {
+        let (_, range) = self
+            .records()
+            .into_iter()
+            .find(|(tag, _)| *tag == CIFF_IMAGE_INFO_TAG)?;
+        if range.len() < 16 {
+            return None;
+        }
+
+        match self.u32(range.start + 12)? as i32 {
+            90 | -270 => Some(Orientation::Rotate90),
+            180 | -180 => Some(Orientation::Rotate180),
+            270 | -90 => Some(Orientation::Rotate270),
+            _ => Some(Orientation::NoTransforms),
+        }
+    }
+}
+
+/// Returns the types of the iso-bmff boxes in the range with the ranges of their contents
+fn iso_boxes(buf: &[u8], range: Range<usize>) -> Vec<([u8; 4], Range<usize>)> {
+    let mut boxes = Vec::new();
+    let mut position = range.start;
+
+    while position + 8 <= range.end {
+        let Some(size) = buf
+            .get(position..position + 4)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u32::from_be_bytes)
+        else {
+            break;
+        };
+        // boxes extending to the end of the file and 64 bit sizes aren't used by canon
+        if size < 8 {
+            break;
+        }
+        let kind = buf[position + 4..position + 8]
+            .try_into()
+            .unwrap_or_default();
+        let end = position.saturating_add(size as usize).min(range.end);
+        boxes.push((kind, position + 8..end));
+        position = end;
+    }
+
+    boxes
+}
+
+/// Parses the jpeg stream at the offset if it starts there and fits into the length
+fn parse_jpeg_at(buf: &[u8], offset: u32, length: u32) -> Option<Preview> {
+    let start = offset as usize;
+    let end = start.checked_add(length as usize)?;
+    let buf = buf.get(..end)?;
+    if buf.get(start..start + 2)? != [0xFF, 0xD8] {
+        return None;
+    }
+
+    parse_jpeg(buf, start)
+}
+
+/// Walks the segments of a jpeg stream starting at the given offset.
+/// Returns `None` if the stream is incomplete or uses an encoding that can't be decoded.
+fn parse_jpeg(buf: &[u8], start: usize) -> Option<Preview> {
+    let mut position = start + 2;
+    let mut dimensions = None;
+
+    loop {
+        if *buf.get(position)? != 0xFF {
+            return None;
+        }
+        // markers can be preceded by any number of fill bytes
+        while *buf.get(position + 1)? == 0xFF {
+            position += 1;
+        }
+        let marker = buf[position + 1];
+        position += 2;
+
+        match marker {
+            0xD9 => {
+                let (width, height) = dimensions?;
+                return Some(Preview {
+                    range: start..position,
+                    width,
+                    height,
+                });
+            }
+            0x01 | 0xD0..=0xD7 => continue,
+            // lossless and arithmetic coded jpegs (e.g. raw sensor data) can't be decoded
+            0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => return None,
+            _ => {}
+        }
+        let length = usize::from(u16::from_be_bytes([
+            *buf.get(position)?,
+            *buf.get(position + 1)?,
+        ]));
+        if length < 2 {
+            return None;
+        }
+        let segment = buf.get(position..position + length)?;
+
+        if matches!(marker, 0xC0..=0xC2) {
+            let height = u16::from_be_bytes([*segment.get(3)?, *segment.get(4)?]);
+            let width = u16::from_be_bytes([*segment.get(5)?, *segment.get(6)?]);
+            if width == 0 || height == 0 {
+                return None;
+            }
+            dimensions = Some((width, height));
+        }
+        position += length;
+
+        if marker == 0xDA {
+            position = skip_entropy_coded_data(buf, position)?;
+        }
+    }
+}
+
+/// Returns the position of the first marker after the entropy coded data of a scan
+fn skip_entropy_coded_data(buf: &[u8], mut position: usize) -> Option<usize> {
+    loop {
+        position += buf.get(position..)?.iter().position(|&b| b == 0xFF)?;
+        match *buf.get(position + 1)? {
+            // stuffed bytes, restart markers and fill bytes are part of the scan
+            0x00 | 0xD0..=0xD7 | 0xFF => position += 1,
+            _ => return Some(position),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::error::{ThumbError, ThumbResult};
+use crate::size::ThumbnailSize;
+use image::{DynamicImage, RgbaImage};
+use rayon::prelude::*;
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg::{fontdb, Options, Tree};
+use std::io::Read;
+use std::sync::Arc;
+
+lazy_static::lazy_static! {
+    static ref FONT_DB: Arc<fontdb::Database> = {
+        let mut db = fontdb::Database::new();
+        db.load_system_fonts();
+        Arc::new(db)
+    };
+}
+
+/// Renders an svg at its intrinsic size
+pub fn read_svg_image<R: Read>(reader: R) -> ThumbResult<DynamicImage> {
+    let tree = parse_svg(reader)?;
+    let size = tree.size().to_int_size();
+
+    render_tree(&tree, size.width(), size.height())
+}
+
+/// Renders an svg once for every requested size so that no detail is lost by downscaling.
+/// The aspect ratio of the svg is kept, so the resulting image fits into the requested size.
+pub fn render_svg_images<R: Read>(
+    reader: R,
+    sizes: &[ThumbnailSize],
+) -> ThumbResult<Vec<DynamicImage>> {
+    let tree = parse_svg(reader)?;
+    let tree_size = tree.size();
+
+    sizes
+        .into_par_iter()
+        .map(|size| {
+            let (width, height) = size.dimensions();
+            let scale = (width as f32 / tree_size.width()).min(height as f32 / tree_size.height());
+            let width = ((tree_size.width() * scale).round() as u32).max(1);
+            let height = ((tree_size.height() * scale).round() as u32).max(1);
+
+            render_tree(&tree, width, height)
+        })
+        .collect()
+}
+
+fn parse_svg<R: Read>(mut reader: R) -> ThumbResult<Tree> {
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+    let options = Options {
+        fontdb: FONT_DB.clone(),
+        ..Options::default()
+    };
+
+    Tree::from_data(&buf, &options).map_err(|_| ThumbError::Decode)
+}
+
+/// Renders the tree scaled to the given dimensions onto a transparent canvas
+fn render_tree(tree: &Tree, width: u32, height: u32) -> ThumbResult<DynamicImage> {
+    let mut pixmap = Pixmap::new(width, height).ok_or(ThumbError::Decode)?;
+    let transform = Transform::from_scale(
+        width as f32 / tree.size().width(),
+        height as f32 / tree.size().height(),
+    );
+    resvg::render(tree, transform, &mut pixmap.as_mut());
+
+    // tiny-skia stores premultiplied pixels while image expects straight alpha
+    let data = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    let image = RgbaImage::from_raw(width, height, data).ok_or(ThumbError::Decode)?;
+
+    Ok(DynamicImage::ImageRgba8(image))
+}
@@ -1,11 +1,12 @@
-use crate::error::{self, ThumbError, ThumbResult};
-use crate::utils::ffmpeg_cli::{get_png_frame, is_ffmpeg_installed};
+use crate::error::{ThumbError, ThumbResult};
+use crate::tone_mapping::{hdr_to_linear, HdrTransfer};
+use crate::utils::ffmpeg_cli::{
+    get_color_transfer, get_png16_frame, get_png_frame, is_ffmpeg_installed,
+};
 use file_format::FileFormat;
-use image::io::Reader as ImageReader;
-use image::{DynamicImage, ImageFormat};
-use std::error::Error;
+use image::{DynamicImage, ImageFormat, ImageReader};
 use std::fs;
-use std::io::{BufRead, Cursor, ErrorKind, Seek};
+use std::io::{BufRead, Cursor, Seek};
 use std::path::PathBuf;
 
 pub fn get_video_frame<R: BufRead + Seek>(
@@ -29,13 +30,22 @@ pub fn get_video_frame<R: BufRead + Seek>(
     let mut buf = Vec::new();
     reader.read_to_end(&mut buf)?;
 
-    let png_bytes = get_png_frame(
-        path.to_str()
-            .expect("path to tmpdir contains invalid characters"),
-        16,
-    )?; // take the 16th frame
+    let path = path
+        .to_str()
+        .expect("path to tmpdir contains invalid characters");
+    // hdr frames are read with 16 bits and converted to linear light for tone mapping
+    let transfer = match get_color_transfer(path).ok().as_deref() {
+        Some("smpte2084") => Some(HdrTransfer::Pq),
+        Some("arib-std-b67") => Some(HdrTransfer::Hlg),
+        _ => None,
+    };
+    let png_bytes = if transfer.is_some() {
+        get_png16_frame(path, 16)?
+    } else {
+        get_png_frame(path, 16)?
+    }; // take the 16th frame
     tempdir.close()?;
     let img = ImageReader::with_format(Cursor::new(png_bytes), ImageFormat::Png).decode()?;
 
-    Ok(img)
+    Ok(hdr_to_linear(img, transfer))
 }
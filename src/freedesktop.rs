@@ -0,0 +1,275 @@
+//! Thumbnail cache following the freedesktop thumbnail managing standard
+//! that is shared with file managers like nautilus and dolphin.
+//! See <https://specifications.freedesktop.org/thumbnail-spec/latest/>
+
+use crate::error::{ThumbError, ThumbResult};
+use crate::{
+    create_thumbnails_with_preview, Background, Copyright, PngOptions, Thumbnail, ThumbnailOptions,
+    ToneMapping,
+};
+use file_format::FileFormat;
+use image::{DynamicImage, RgbaImage};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+const URI_KEY: &str = "Thumb::URI";
+const MTIME_KEY: &str = "Thumb::MTime";
+const SIZE_KEY: &str = "Thumb::Size";
+const MIME_KEY: &str = "Thumb::Mime";
+
+/// The size directories of the cache with the maximum edge length of their thumbnails
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CacheSize {
+    /// 128 pixels
+    Normal,
+    /// 256 pixels
+    Large,
+    /// 512 pixels
+    XLarge,
+    /// 1024 pixels
+    XXLarge,
+}
+
+impl CacheSize {
+    pub const fn max_edge(&self) -> u32 {
+        match self {
+            Self::Normal => 128,
+            Self::Large => 256,
+            Self::XLarge => 512,
+            Self::XXLarge => 1024,
+        }
+    }
+
+    pub const fn dir_name(&self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Large => "large",
+            Self::XLarge => "x-large",
+            Self::XXLarge => "xx-large",
+        }
+    }
+}
+
+/// A thumbnail directory like `$XDG_CACHE_HOME/thumbnails`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ThumbnailCache {
+    root: PathBuf,
+}
+
+/// The attributes of a file that are stored with its thumbnail as text
+struct SourceFile {
+    uri: String,
+    /// The modification time in seconds since the unix epoch
+    mtime: String,
+    /// The size in bytes
+    size: String,
+}
+
+impl ThumbnailCache {
+    /// Uses the given directory as the root of the cache
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Uses `$XDG_CACHE_HOME/thumbnails` or `$HOME/.cache/thumbnails` if the former isn't set
+    pub fn from_env() -> Option<Self> {
+        let cache_home = std::env::var_os("XDG_CACHE_HOME")
+            .filter(|path| Path::new(path).is_absolute())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?;
+
+        Some(Self::new(cache_home.join("thumbnails")))
+    }
+
+    /// The directory containing the size directories
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the path of the thumbnail for the uri whether it exists or not
+    pub fn thumbnail_path(&self, uri: &str, size: CacheSize) -> PathBuf {
+        self.root.join(size.dir_name()).join(file_name(uri))
+    }
+
+    /// Returns the path of the failure entry the application records for the uri
+    pub fn failure_path(&self, uri: &str, app: &str) -> PathBuf {
+        self.root.join("fail").join(app).join(file_name(uri))
+    }
+
+    /// Returns the path of the cached thumbnail if it exists and the file
+    /// hasn't been modified since the thumbnail was created
+    pub fn lookup<P: AsRef<Path>>(&self, file: P, size: CacheSize) -> ThumbResult<Option<PathBuf>> {
+        let source = SourceFile::read(file.as_ref())?;
+        let path = self.thumbnail_path(&source.uri, size);
+
+        Ok(source.is_described_by(&path).then_some(path))
+    }
+
+    /// Creates the thumbnail of the file and stores it in the cache
+    pub fn create<P: AsRef<Path>>(&self, file: P, size: CacheSize) -> ThumbResult<PathBuf> {
+        self.create_with_options(file, size, &ThumbnailOptions::default())
+    }
+
+    /// Creates the thumbnail of the file with the given options and stores it in the cache.
+    /// The thumbnail keeps the aspect ratio of the file and isn't larger than the file itself.
+    pub fn create_with_options<P: AsRef<Path>>(
+        &self,
+        file: P,
+        size: CacheSize,
+        options: &ThumbnailOptions,
+    ) -> ThumbResult<PathBuf> {
+        let file = file.as_ref();
+        let source = SourceFile::read(file)?;
+        let mime = FileFormat::from_file(file)?;
+        let reader = BufReader::new(File::open(file)?);
+        let (_, preview) =
+            create_thumbnails_with_preview(reader, mime, [], options, Some(size.max_edge()))?;
+        let thumbnail = Thumbnail {
+            inner: preview.ok_or(ThumbError::Decode)?,
+            mime,
+            tone_mapping: options.tone_mapping,
+            background: Background::default(),
+            color_profile: options
+                .embed_color_profile
+                .then(|| options.color_profile.clone()),
+            copyright: Copyright::default(),
+            options: options.clone(),
+        };
+        let path = self.thumbnail_path(&source.uri, size);
+        let mut text = source.text();
+        text.push((SIZE_KEY, &source.size));
+        text.push((MIME_KEY, mime.media_type()));
+        write_entry(&path, &thumbnail, &text)?;
+
+        Ok(path)
+    }
+
+    /// Returns whether the application failed to create a thumbnail
+    /// of the file in its current version
+    pub fn has_failed<P: AsRef<Path>>(&self, file: P, app: &str) -> ThumbResult<bool> {
+        let source = SourceFile::read(file.as_ref())?;
+
+        Ok(source.is_described_by(&self.failure_path(&source.uri, app)))
+    }
+
+    /// Records that the application failed to create a thumbnail of the file, so other
+    /// attempts can be skipped until the file is modified. The application name should
+    /// include its version, e.g. `my-app-1.0`.
+    pub fn record_failure<P: AsRef<Path>>(&self, file: P, app: &str) -> ThumbResult<PathBuf> {
+        let source = SourceFile::read(file.as_ref())?;
+        let path = self.failure_path(&source.uri, app);
+        let thumbnail = Thumbnail {
+            inner: DynamicImage::ImageRgba8(RgbaImage::new(1, 1)),
+            mime: FileFormat::ArbitraryBinaryData,
+            tone_mapping: ToneMapping::default(),
+            background: Background::default(),
+            color_profile: None,
+            copyright: Copyright::default(),
+            options: ThumbnailOptions::default(),
+        };
+        write_entry(&path, &thumbnail, &source.text())?;
+
+        Ok(path)
+    }
+}
+
+impl SourceFile {
+    fn read(path: &Path) -> ThumbResult<Self> {
+        let metadata = fs::metadata(path)?;
+        let mtime = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map_err(io::Error::other)?
+            .as_secs();
+
+        Ok(Self {
+            uri: file_uri(path)?,
+            mtime: mtime.to_string(),
+            size: metadata.len().to_string(),
+        })
+    }
+
+    /// The chunks every entry is required to have
+    fn text(&self) -> Vec<(&str, &str)> {
+        vec![(URI_KEY, &self.uri), (MTIME_KEY, &self.mtime)]
+    }
+
+    /// Returns whether the png at the path is an entry of this file in its current version
+    fn is_described_by(&self, path: &Path) -> bool {
+        let Some(text) = read_text(path) else {
+            return false;
+        };
+
+        text.get(URI_KEY) == Some(&self.uri)
+            && text.get(MTIME_KEY) == Some(&self.mtime)
+            && text.get(SIZE_KEY).is_none_or(|size| *size == self.size)
+    }
+}
+
+/// Returns the canonical `file://` uri of the path as used to name the cache entries
+pub fn file_uri<P: AsRef<Path>>(path: P) -> ThumbResult<String> {
+    let path = fs::canonicalize(path)?;
+    #[cfg(unix)]
+    let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()).to_vec();
+    #[cfg(not(unix))]
+    let bytes = format!("/{}", path.to_string_lossy().replace('\\', "/")).into_bytes();
+    let mut uri = String::from("file://");
+
+    for byte in bytes {
+        // the characters glib leaves unescaped in paths
+        if byte.is_ascii_alphanumeric() || b"!$&'()*+,-./:=@_~".contains(&byte) {
+            uri.push(char::from(byte));
+        } else {
+            uri.push_str(&format!("%{byte:02X}"));
+        }
+    }
+
+    Ok(uri)
+}
+
+/// The file name of cache entries is the md5 hash of the uri
+fn file_name(uri: &str) -> String {
+    format!("{:x}.png", md5::compute(uri))
+}
+
+fn read_text(path: &Path) -> Option<HashMap<String, String>> {
+    let decoder = png::Decoder::new(BufReader::new(File::open(path).ok()?));
+    let reader = decoder.read_info().ok()?;
+
+    Some(
+        reader
+            .info()
+            .uncompressed_latin1_text
+            .iter()
+            .map(|chunk| (chunk.keyword.clone(), chunk.text.clone()))
+            .collect(),
+    )
+}
+
+/// Writes the entry into a temporary file that is renamed afterwards,
+/// so other applications never read an incomplete thumbnail
+fn write_entry(path: &Path, thumbnail: &Thumbnail, text: &[(&str, &str)]) -> ThumbResult<()> {
+    let dir = path
+        .parent()
+        .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?;
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir)?;
+
+    // temporary files are only readable by the owner as required by the standard
+    let mut file = tempfile::NamedTempFile::new_in(dir)?;
+    // the standard only allows 8 bit rgba images
+    let options = PngOptions {
+        reduce: false,
+        ..Default::default()
+    };
+    thumbnail.encode_png(file.as_file_mut(), &options, text)?;
+    file.persist(path).map_err(|e| e.error)?;
+
+    Ok(())
+}
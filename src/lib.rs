@@ -17,25 +17,65 @@
 //! let mut buf = Cursor::new(Vec::new());
 //! thumbnail.write_png(&mut buf).unwrap();
 //! ```
-use crate::{error::ThumbResult, utils::ffmpeg_cli::get_webp_frame};
-use file_format::FileFormat;
-use image::{DynamicImage, GenericImageView, ImageFormat};
+use crate::error::ThumbResult;
+use file_format::{FileFormat, Kind};
+use image::{ColorType, DynamicImage, GenericImageView, ImageEncoder, RgbaImage};
 use rayon::prelude::*;
 use std::io::{BufRead, BufReader, Seek, Write};
 
-use crate::formats::get_base_image;
+use crate::formats::{get_pdf_page_images, get_sized_images_with_preview};
+use adjustments::apply_adjustments;
+pub use adjustments::Adjustment;
+pub use alpha::Background;
+use alpha::{flatten, premultiply, unpremultiply};
+pub use byte_budget::{BudgetedImage, ByteBudget};
+pub use color_profile::ColorProfile;
+pub use copyright::Copyright;
+pub use encoding::{
+    AvifOptions, ChromaSubsampling, JpegOptions, OutputFormat, PngFilter, PngOptions,
+    PngQuantization, WebpOptions,
+};
+use linear_light::{from_linear, to_linear};
+pub use lqip::LqipOptions;
+pub use palette::PaletteColor;
+pub use perceptual_hash::ImageHash;
+pub use placeholder::{blurhash_to_thumbnail, thumbhash_to_thumbnail, Placeholders};
 pub use size::ThumbnailSize;
 use std::convert::From;
+pub use tone_mapping::ToneMapping;
+use tone_mapping::{is_hdr, is_high_bit_depth, to_rgba16, to_rgba8};
 
+mod adjustments;
+mod alpha;
+mod byte_budget;
+mod color_profile;
+mod copyright;
+mod encoding;
 pub mod error;
+#[cfg(feature = "fast_resize")]
+mod fast_resize;
 mod formats;
+pub mod freedesktop;
+mod linear_light;
+mod lqip;
+mod palette;
+mod perceptual_hash;
+mod placeholder;
+mod png_writer;
 mod size;
+mod tone_mapping;
 pub(crate) mod utils;
 
 #[derive(Clone, Debug)]
 pub struct Thumbnail {
     inner: DynamicImage,
     mime: FileFormat,
+    tone_mapping: ToneMapping,
+    background: Background,
+    color_profile: Option<ColorProfile>,
+    copyright: Copyright,
+    /// The options the thumbnail was created with that are reused when it's resized again
+    options: ThumbnailOptions,
 }
 
 #[derive(Clone, Debug)]
@@ -65,35 +105,274 @@ impl From<FilterType> for image::imageops::FilterType {
     }
 }
 
+/// Implementations used to resize images
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResizeBackend {
+    /// The filters of the image crate
+    #[default]
+    Image,
+    /// SIMD accelerated convolution which produces results within a small
+    /// tolerance of the image crate's filters
+    #[cfg(feature = "fast_resize")]
+    Simd,
+}
+
+/// Options used when creating thumbnails
+#[derive(Clone, Debug)]
+pub struct ThumbnailOptions {
+    /// The filter used to resize the images
+    pub filter: FilterType,
+    /// The color profile images are converted to before they are resized
+    pub color_profile: ColorProfile,
+    /// Embeds the color profile into the written thumbnails
+    pub embed_color_profile: bool,
+    /// Resizes the images in linear light instead of their srgb encoded values.
+    /// This keeps the brightness of fine high contrast details like text.
+    pub linear_light: bool,
+    /// The implementation used to resize the images
+    pub backend: ResizeBackend,
+    /// Adjustments applied in order to every thumbnail after resizing
+    pub adjustments: Vec<Adjustment>,
+    /// Reads the copyright notices from the exif data of image files,
+    /// so they can be kept with [`JpegOptions::keep_copyright`]
+    pub read_copyright: bool,
+    /// The operator used to map hdr images to 8 bits when writing the thumbnails
+    pub tone_mapping: ToneMapping,
+}
+
+impl Default for ThumbnailOptions {
+    fn default() -> Self {
+        Self {
+            filter: FilterType::Lanczos3,
+            color_profile: ColorProfile::default(),
+            embed_color_profile: false,
+            linear_light: false,
+            backend: ResizeBackend::default(),
+            adjustments: Vec::new(),
+            read_copyright: false,
+            tone_mapping: ToneMapping::default(),
+        }
+    }
+}
+
 impl Thumbnail {
-    /// Writes the bytes of the image in a png format
-    pub fn write_png<W: Write + Seek>(self, writer: &mut W) -> ThumbResult<()> {
-        let image = DynamicImage::ImageRgba8(self.inner.into_rgba8());
-        image.write_to(writer, ImageFormat::Png)?;
+    /// Encodes the image in the given format without consuming the thumbnail
+    pub fn encode<W: Write>(&self, format: &OutputFormat, writer: &mut W) -> ThumbResult<()> {
+        match format {
+            OutputFormat::Png(options) => self.encode_png(writer, options, &[]),
+            OutputFormat::Jpeg(options) => self.encode_jpeg(writer, options),
+            #[cfg(feature = "webp")]
+            OutputFormat::Webp(options) => self.encode_webp(writer, options),
+            #[cfg(feature = "avif")]
+            OutputFormat::Avif(options) => self.encode_avif(writer, options),
+            OutputFormat::Gif => self.encode_rgba8(image::codecs::gif::GifEncoder::new(writer)),
+            #[cfg(feature = "qoi")]
+            OutputFormat::Qoi => self.encode_rgba8(image::codecs::qoi::QoiEncoder::new(writer)),
+            #[cfg(feature = "bmp")]
+            OutputFormat::Bmp => self.encode_rgba8(image::codecs::bmp::BmpEncoder::new(writer)),
+        }
+    }
 
-        Ok(())
+    /// Writes the bytes of the image in a png format as rgba.
+    /// Smaller files can be written with [`OutputFormat::Png`] and the default options.
+    pub fn write_png<W: Write>(self, writer: &mut W) -> ThumbResult<()> {
+        let options = PngOptions {
+            reduce: false,
+            ..Default::default()
+        };
+        self.encode(&OutputFormat::Png(options), writer)
     }
 
-    /// Writes the bytes of the image in a jpeg format
-    pub fn write_jpeg<W: Write + Seek>(self, writer: &mut W, quality: u8) -> ThumbResult<()> {
-        let image = DynamicImage::ImageRgb8(self.inner.into_rgb8());
-        let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(writer, quality);
-        encoder.encode_image(&image)?;
+    /// Writes the bytes of the image in a png format as rgba with 16 bits per channel
+    /// if the source image has more than 8 bits per channel
+    pub fn write_png16<W: Write>(self, writer: &mut W) -> ThumbResult<()> {
+        let options = PngOptions {
+            reduce: false,
+            high_bit_depth: true,
+            ..Default::default()
+        };
+        self.encode(&OutputFormat::Png(options), writer)
+    }
 
-        Ok(())
+    /// Writes the bytes of the image in a jpeg format.
+    /// Transparent areas are flattened onto the background of the thumbnail.
+    pub fn write_jpeg<W: Write>(self, writer: &mut W, quality: u8) -> ThumbResult<()> {
+        let options = JpegOptions {
+            quality,
+            ..Default::default()
+        };
+        self.encode(&OutputFormat::Jpeg(options), writer)
     }
+
     /// Writes the bytes of the image in a webp format
     #[cfg(feature = "webp")]
-    pub fn write_webp<W: Write + Seek>(self, writer: &mut W) -> ThumbResult<()> {
+    pub fn write_webp<W: Write>(self, writer: &mut W, options: &WebpOptions) -> ThumbResult<()> {
+        self.encode(&OutputFormat::Webp(options.clone()), writer)
+    }
+
+    /// Writes the bytes of the image in an avif format. The encoder can only signal srgb,
+    /// so thumbnails converted to other color profiles can't be written.
+    #[cfg(feature = "avif")]
+    pub fn write_avif<W: Write>(self, writer: &mut W, options: &AvifOptions) -> ThumbResult<()> {
+        self.encode(&OutputFormat::Avif(options.clone()), writer)
+    }
+
+    /// Encodes the image as png with the given tEXt chunks
+    fn encode_png<W: Write>(
+        &self,
+        writer: &mut W,
+        options: &PngOptions,
+        text: &[(&str, &str)],
+    ) -> ThumbResult<()> {
+        let icc_profile = self.icc_profile()?;
+        if options.high_bit_depth
+            && options.quantization.is_none()
+            && is_high_bit_depth(&self.inner)
+        {
+            let image = to_rgba16(&self.inner, self.tone_mapping, &self.options.color_profile)
+                .into_rgba16();
+            return png_writer::write_png16(&image, icc_profile, text, options, writer);
+        }
+        let image = self.rgba8();
+        png_writer::write_png(&image, icc_profile, text, options, writer)
+    }
+
+    fn encode_jpeg<W: Write>(&self, writer: &mut W, options: &JpegOptions) -> ThumbResult<()> {
+        use jpeg_encoder::SamplingFactor;
+        let icc_profile = self.icc_profile()?;
+        let image = flatten(&self.rgba8(), self.background);
+        let (width, height) = image.dimensions();
+        let mut encoder = jpeg_encoder::Encoder::new(writer, options.quality.clamp(1, 100));
+        encoder.set_sampling_factor(match options.subsampling {
+            ChromaSubsampling::Yuv444 => SamplingFactor::R_4_4_4,
+            ChromaSubsampling::Yuv422 => SamplingFactor::R_4_2_2,
+            ChromaSubsampling::Yuv420 => SamplingFactor::R_4_2_0,
+        });
+        encoder.set_progressive(options.progressive);
+        encoder.set_optimized_huffman_tables(options.optimize_huffman_tables);
+        if let Some(icc_profile) = icc_profile {
+            encoder.add_icc_profile(&icc_profile)?;
+        }
+        if options.keep_copyright {
+            if let Some(exif) = utils::exif::write_copyright(&self.copyright) {
+                encoder.add_exif_metadata(&exif)?;
+            }
+        }
+        let dimension = |value: u32| {
+            u16::try_from(value).map_err(|_| {
+                image::ImageError::Limits(image::error::LimitError::from_kind(
+                    image::error::LimitErrorKind::DimensionError,
+                ))
+            })
+        };
+        encoder.encode(
+            image.as_raw(),
+            dimension(width)?,
+            dimension(height)?,
+            jpeg_encoder::ColorType::Rgb,
+        )?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "webp")]
+    fn encode_webp<W: Write>(&self, writer: &mut W, options: &WebpOptions) -> ThumbResult<()> {
+        use crate::error::ThumbError;
         use image::EncodableLayout;
-        use webp;
-        let image = DynamicImage::ImageRgba8(self.inner.into_rgba8());
-        let mut webp = webp::Encoder::from_image(&image).unwrap();
-        let out = webp.encode(70.0);
-        writer.write_all(out.as_bytes());
+        let icc_profile = self.icc_profile()?;
+        let image = DynamicImage::ImageRgba8(self.rgba8());
+        let config = options
+            .to_config()
+            .ok_or_else(|| ThumbError::Webp(String::from("invalid encoder config")))?;
+        let webp =
+            webp::Encoder::from_image(&image).map_err(|e| ThumbError::Webp(e.to_string()))?;
+        let out = webp
+            .encode_advanced(&config)
+            .map_err(|e| ThumbError::Webp(format!("{e:?}")))?;
+        if let Some(icc_profile) = icc_profile {
+            let out = utils::webp_container::embed_icc_profile(
+                out.as_bytes(),
+                &icc_profile,
+                image.width(),
+                image.height(),
+            );
+            writer.write_all(&out)?;
+        } else {
+            writer.write_all(out.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "avif")]
+    fn encode_avif<W: Write>(&self, writer: &mut W, options: &AvifOptions) -> ThumbResult<()> {
+        use crate::error::ThumbError;
+        // the color values would be misinterpreted as srgb
+        if self.options.color_profile != ColorProfile::Srgb {
+            return Err(ThumbError::Avif(String::from(
+                "only srgb thumbnails can be encoded",
+            )));
+        }
+        let image = self.rgba8();
+        let (width, height) = image.dimensions();
+        let pixels = image
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b, a] = pixel.0;
+                ravif::RGBA8::new(r, g, b, a)
+            })
+            .collect::<Vec<_>>();
+        let encoded = options
+            .to_encoder()
+            .encode_rgba(ravif::Img::new(
+                &pixels[..],
+                width as usize,
+                height as usize,
+            ))
+            .map_err(|e| ThumbError::Avif(e.to_string()))?;
+        writer.write_all(&encoded.avif_file)?;
+
+        Ok(())
+    }
+
+    /// Encodes the image as 8 bit rgba with an encoder that doesn't support color profiles
+    fn encode_rgba8<E: ImageEncoder>(&self, encoder: E) -> ThumbResult<()> {
+        let image = DynamicImage::ImageRgba8(self.rgba8());
+        image.write_with_encoder(encoder)?;
+
         Ok(())
     }
 
+    /// Sets the operator used to map hdr images to 8 bits when writing them,
+    /// overriding [`ThumbnailOptions::tone_mapping`]
+    pub fn set_tone_mapping(&mut self, tone_mapping: ToneMapping) {
+        self.tone_mapping = tone_mapping;
+    }
+
+    /// Sets the background transparent images are flattened onto when
+    /// they are written in formats without an alpha channel
+    pub fn set_background(&mut self, background: Background) {
+        self.background = background;
+    }
+
+    /// Returns the image as 8 bit rgba, tone mapping hdr images
+    fn rgba8(&self) -> RgbaImage {
+        to_rgba8(&self.inner, self.tone_mapping, &self.options.color_profile)
+    }
+
+    /// Returns the icc profile that is embedded into the written images
+    fn icc_profile(&self) -> ThumbResult<Option<Vec<u8>>> {
+        self.color_profile
+            .as_ref()
+            .map(ColorProfile::icc_bytes)
+            .transpose()
+    }
+
+    /// Returns the copyright notices read from the exif data of the source image.
+    /// They are empty unless enabled with [`ThumbnailOptions::read_copyright`].
+    pub fn copyright(&self) -> &Copyright {
+        &self.copyright
+    }
+
     /// Returns the fileformat that it's parsed
     pub fn return_fileformat(&self) -> FileFormat {
         self.mime
@@ -113,14 +392,88 @@ pub fn create_thumbnails_samplefilter<R: BufRead + Seek, I: IntoIterator<Item =
     sizes: I,
     filter: FilterType,
 ) -> ThumbResult<Vec<Thumbnail>> {
-    let image = get_base_image(reader, mime)?;
+    let options = ThumbnailOptions {
+        filter,
+        ..Default::default()
+    };
+    create_thumbnails_with_options(reader, mime, sizes, &options)
+}
+
+/// Creates thumbnails of the requested sizes for the given reader providing the content as bytes and
+/// the mime describing the contents type using the given options
+pub fn create_thumbnails_with_options<R: BufRead + Seek, I: IntoIterator<Item = ThumbnailSize>>(
+    reader: R,
+    mime: FileFormat,
+    sizes: I,
+    options: &ThumbnailOptions,
+) -> ThumbResult<Vec<Thumbnail>> {
+    let (thumbnails, _) = create_thumbnails_with_preview(reader, mime, sizes, options, None)?;
+
+    Ok(thumbnails)
+}
+
+/// Creates thumbnails of the requested sizes together with the BlurHash and ThumbHash
+/// placeholders of the file. The placeholders are computed from the same decoded image.
+pub fn create_thumbnails_with_placeholders<
+    R: BufRead + Seek,
+    I: IntoIterator<Item = ThumbnailSize>,
+>(
+    reader: R,
+    mime: FileFormat,
+    sizes: I,
+    options: &ThumbnailOptions,
+) -> ThumbResult<(Vec<Thumbnail>, Placeholders)> {
+    use crate::error::ThumbError;
+
+    let (thumbnails, preview) = create_thumbnails_with_preview(
+        reader,
+        mime,
+        sizes,
+        options,
+        Some(placeholder::PREVIEW_SIZE),
+    )?;
+    let preview = preview.ok_or(ThumbError::Decode)?;
+
+    Ok((
+        thumbnails,
+        Placeholders::from_preview(&preview, options.tone_mapping, &options.color_profile)?,
+    ))
+}
+
+fn create_thumbnails_with_preview<R: BufRead + Seek, I: IntoIterator<Item = ThumbnailSize>>(
+    mut reader: R,
+    mime: FileFormat,
+    sizes: I,
+    options: &ThumbnailOptions,
+    preview_size: Option<u32>,
+) -> ThumbResult<(Vec<Thumbnail>, Option<DynamicImage>)> {
     let sizes: Vec<ThumbnailSize> = sizes.into_iter().collect();
-    let thumbnails = resize_images(image, &sizes, filter)
-        .into_iter()
-        .map(|image| Thumbnail { inner: image, mime })
+    let copyright = if options.read_copyright && mime.kind() == Kind::Image {
+        utils::exif::read_copyright(&mut reader)?
+    } else {
+        Copyright::default()
+    };
+    let color_profile = options
+        .embed_color_profile
+        .then(|| options.color_profile.clone());
+    let (images, preview) =
+        get_sized_images_with_preview(reader, mime, &sizes, options, preview_size)?;
+    let thumbnails = images
+        .into_par_iter()
+        .map(|image| Thumbnail {
+            inner: apply_adjustments(image, &options.adjustments),
+            mime,
+            tone_mapping: options.tone_mapping,
+            background: Background::default(),
+            color_profile: color_profile.clone(),
+            copyright: copyright.clone(),
+            options: options.clone(),
+        })
         .collect();
+    // the placeholders should look like the adjusted thumbnails
+    let preview = preview.map(|image| apply_adjustments(image, &options.adjustments));
 
-    Ok(thumbnails)
+    Ok((thumbnails, preview))
 }
 
 /// Creates thumbnails of the requested sizes for the given reader providing the content as bytes and
@@ -130,11 +483,28 @@ pub fn create_thumbnails<R: BufRead + Seek, I: IntoIterator<Item = ThumbnailSize
     mime: FileFormat,
     sizes: I,
 ) -> ThumbResult<Vec<Thumbnail>> {
-    let image = get_base_image(reader, mime)?;
+    create_thumbnails_with_options(reader, mime, sizes, &ThumbnailOptions::default())
+}
+
+/// Creates thumbnails of the requested sizes for the given page of a pdf document.
+/// The page index starts at 1.
+pub fn create_pdf_page_thumbnails<R: BufRead + Seek, I: IntoIterator<Item = ThumbnailSize>>(
+    reader: R,
+    page: usize,
+    sizes: I,
+) -> ThumbResult<Vec<Thumbnail>> {
     let sizes: Vec<ThumbnailSize> = sizes.into_iter().collect();
-    let thumbnails = resize_images(image, &sizes, FilterType::Lanczos3)
+    let thumbnails = get_pdf_page_images(reader, page, &sizes, &ThumbnailOptions::default())?
         .into_iter()
-        .map(|image| Thumbnail { inner: image, mime })
+        .map(|image| Thumbnail {
+            inner: image,
+            mime: FileFormat::PortableDocumentFormat,
+            tone_mapping: ToneMapping::default(),
+            background: Background::default(),
+            color_profile: None,
+            copyright: Copyright::default(),
+            options: ThumbnailOptions::default(),
+        })
         .collect();
 
     Ok(thumbnails)
@@ -150,38 +520,92 @@ pub fn create_thumbnails_unknown_type<R: BufRead + Seek, I: IntoIterator<Item =
     let mut temp = BufReader::new(reader);
     let mut temp1 = temp.fill_buf().unwrap();
     let le = temp1.len();
-    let temp2 = &temp1[..];
-    let mime = FileFormat::from_bytes(temp2);
+    let mime = FileFormat::from_bytes(temp1);
     temp1.consume(le);
 
-    let image = get_base_image(temp, mime)?;
-    let sizes: Vec<ThumbnailSize> = sizes.into_iter().collect();
-    let thumbnails = resize_images(image, &sizes, FilterType::Lanczos3)
-        .into_iter()
-        .map(|image| Thumbnail { inner: image, mime })
-        .collect();
-
-    Ok(thumbnails)
+    create_thumbnails_with_options(temp, mime, sizes, &ThumbnailOptions::default())
 }
 
-fn resize_images(
-    image: DynamicImage,
+/// Resizes the image to all sizes in parallel. Transparent images are resized with
+/// premultiplied alpha and images are resized in linear light if enabled in the options.
+pub(crate) fn resize_images(
+    image: &DynamicImage,
     sizes: &[ThumbnailSize],
-    filter_type: crate::FilterType,
+    options: &ThumbnailOptions,
 ) -> Vec<DynamicImage> {
+    let color = image.color();
+    let source = image.dimensions();
+    // float images already store linear light
+    let linear_light = options.linear_light && !is_hdr(image, None);
+    if !linear_light && !color.has_alpha() {
+        return sizes
+            .into_par_iter()
+            .map(|size| {
+                let (width, height) = size.dimensions_for(source);
+                resize_exact(image, width, height, options)
+            })
+            .collect();
+    }
+
+    let mut image = if linear_light {
+        to_linear(image)
+    } else {
+        DynamicImage::ImageRgba32F(image.to_rgba32f())
+    };
+    // transparent pixels would bleed their hidden color into the edges without premultiplying
+    if let Some(image) = image.as_mut_rgba32f() {
+        premultiply(image);
+    }
+
     sizes
         .into_par_iter()
         .map(|size| {
-            let (width, height) = size.dimensions();
-            image.resize_exact(
-                width,
-                height,
-                image::imageops::FilterType::from(filter_type.clone()),
-            )
+            let (width, height) = size.dimensions_for(source);
+            let mut resized = resize_exact(&image, width, height, options);
+            if let Some(resized) = resized.as_mut_rgba32f() {
+                unpremultiply(resized);
+            }
+            if linear_light {
+                resized = from_linear(resized);
+            }
+            into_color_type(resized, color)
         })
         .collect()
 }
 
+/// Resizes the image with the backend of the options
+fn resize_exact(
+    image: &DynamicImage,
+    width: u32,
+    height: u32,
+    options: &ThumbnailOptions,
+) -> DynamicImage {
+    #[cfg(feature = "fast_resize")]
+    if options.backend == ResizeBackend::Simd {
+        if let Some(resized) = fast_resize::resize_exact(image, width, height, &options.filter) {
+            return resized;
+        }
+    }
+
+    image.resize_exact(width, height, options.filter.clone().into())
+}
+
+/// Converts a float image back to the color type of the source image
+pub(crate) fn into_color_type(image: DynamicImage, color: ColorType) -> DynamicImage {
+    match color {
+        ColorType::L8 => DynamicImage::ImageLuma8(image.into_luma8()),
+        ColorType::La8 => DynamicImage::ImageLumaA8(image.into_luma_alpha8()),
+        ColorType::Rgb8 => DynamicImage::ImageRgb8(image.into_rgb8()),
+        ColorType::Rgba8 => DynamicImage::ImageRgba8(image.into_rgba8()),
+        ColorType::L16 => DynamicImage::ImageLuma16(image.into_luma16()),
+        ColorType::La16 => DynamicImage::ImageLumaA16(image.into_luma_alpha16()),
+        ColorType::Rgb16 => DynamicImage::ImageRgb16(image.into_rgb16()),
+        ColorType::Rgba16 => DynamicImage::ImageRgba16(image.into_rgba16()),
+        ColorType::Rgb32F => DynamicImage::ImageRgb32F(image.into_rgb32f()),
+        _ => DynamicImage::ImageRgba32F(image.into_rgba32f()),
+    }
+}
+
 ///
 /// Get's multiple frames if they exist
 ///
@@ -192,11 +616,10 @@ pub fn get_video_frame_multiple<R: BufRead + Seek>(
     split: usize,              // amount of frames inbetween to get
     scale: Option<(u32, u32)>, // Scales the image
 ) -> ThumbResult<Vec<DynamicImage>> {
-    use crate::error::{self, ThumbError, ThumbResult};
+    use crate::error::ThumbError;
     use crate::utils::ffmpeg_cli::{get_webp_frame, is_ffmpeg_installed};
-    use image::io::Reader as ImageReader;
-    use image::{DynamicImage, ImageFormat};
-    use std::io::{BufRead, Cursor, ErrorKind, Seek};
+    use image::{ImageFormat, ImageReader};
+    use std::io::Cursor;
     lazy_static::lazy_static! { static ref FFMPEG_INSTALLED: bool = is_ffmpeg_installed(); }
     if !*FFMPEG_INSTALLED {
         return Err(ThumbError::Unsupported(mime));
@@ -236,8 +659,12 @@ pub fn get_video_frame_multiple<R: BufRead + Seek>(
             Ok(img) => {
                 if let Some(size) = scale {
                     frames.push(
-                        resize_images(img, &[ThumbnailSize::Custom(size)], FilterType::Lanczos3)[0]
-                            .clone(),
+                        resize_images(
+                            &img,
+                            &[ThumbnailSize::Custom(size)],
+                            &ThumbnailOptions::default(),
+                        )[0]
+                        .clone(),
                     );
                 } else {
                     frames.push(img);
@@ -0,0 +1,50 @@
+use crate::tone_mapping::srgb_encode;
+use image::DynamicImage;
+
+pub(crate) fn srgb_decode(value: f32) -> f32 {
+    if value <= 0.040_45 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Decodes the srgb values of the image into linear light.
+/// Alpha values are kept as they are.
+pub(crate) fn to_linear(image: &DynamicImage) -> DynamicImage {
+    if image.color().has_alpha() {
+        let mut image = image.to_rgba32f();
+        for pixel in image.pixels_mut() {
+            let [r, g, b, a] = pixel.0;
+            pixel.0 = [srgb_decode(r), srgb_decode(g), srgb_decode(b), a];
+        }
+        DynamicImage::ImageRgba32F(image)
+    } else {
+        let mut image = image.to_rgb32f();
+        for pixel in image.pixels_mut() {
+            pixel.0 = pixel.0.map(srgb_decode);
+        }
+        DynamicImage::ImageRgb32F(image)
+    }
+}
+
+/// Encodes a linear light image as srgb
+pub(crate) fn from_linear(image: DynamicImage) -> DynamicImage {
+    match image {
+        DynamicImage::ImageRgba32F(mut image) => {
+            for pixel in image.pixels_mut() {
+                let [r, g, b, a] = pixel.0;
+                pixel.0 = [srgb_encode(r), srgb_encode(g), srgb_encode(b), a]
+                    .map(|value| value.clamp(0.0, 1.0));
+            }
+            DynamicImage::ImageRgba32F(image)
+        }
+        image => {
+            let mut image = image.into_rgb32f();
+            for pixel in image.pixels_mut() {
+                pixel.0 = pixel.0.map(|value| srgb_encode(value).clamp(0.0, 1.0));
+            }
+            DynamicImage::ImageRgb32F(image)
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use crate::color_profile::{convert_color_profile, ColorProfile};
+use crate::error::{ThumbError, ThumbResult};
+use crate::{resize_images, ByteBudget, OutputFormat, Thumbnail, ThumbnailOptions, ThumbnailSize};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+/// Options used when creating low quality image placeholders
+#[derive(Clone, Debug, PartialEq)]
+pub struct LqipOptions {
+    /// The maximum length of the longer edge in pixels
+    pub size: u32,
+    /// The sigma of the gaussian blur applied after resizing where 0 disables blurring
+    pub blur: f32,
+    /// The format of the embedded image
+    pub format: OutputFormat,
+    /// The maximum length of the whole data uri in bytes.
+    /// The quality and dimensions are reduced until the image fits.
+    pub max_bytes: usize,
+}
+
+impl Default for LqipOptions {
+    fn default() -> Self {
+        Self {
+            size: 24,
+            blur: 1.0,
+            format: OutputFormat::Jpeg(Default::default()),
+            max_bytes: 1024,
+        }
+    }
+}
+
+impl Thumbnail {
+    /// Creates a tiny blurred version of the thumbnail encoded as a base64 `data:` uri
+    /// that can be inlined into html or css while the thumbnail is loading
+    pub fn to_data_uri(&self, options: &LqipOptions) -> ThumbResult<String> {
+        let prefix = format!("data:{};base64,", options.format.mime_type());
+        // every three bytes of the image take four base64 characters
+        let max_image_bytes = options.max_bytes.saturating_sub(prefix.len()) / 4 * 3;
+        let budget = ByteBudget {
+            downscale: true,
+            ..ByteBudget::new(max_image_bytes)
+        };
+        let image = self
+            .placeholder_image(options)?
+            .encode_within_budget(&options.format, &budget)
+            .map_err(|e| match e {
+                ThumbError::ExceedsByteBudget(_) => {
+                    ThumbError::ExceedsByteBudget(options.max_bytes)
+                }
+                e => e,
+            })?;
+
+        Ok(prefix + &STANDARD.encode(image.data))
+    }
+
+    /// Returns a copy that fits into the placeholder size and is blurred.
+    /// The color profile is dropped as it would take up most of the byte limit,
+    /// so the colors are converted to srgb.
+    fn placeholder_image(&self, options: &LqipOptions) -> ThumbResult<Self> {
+        let (width, height) = self.size();
+        let scale = (options.size as f32 / width.max(height) as f32).min(1.0);
+        let size = ThumbnailSize::Custom((
+            ((width as f32 * scale).round() as u32).max(1),
+            ((height as f32 * scale).round() as u32).max(1),
+        ));
+        let mut inner = resize_images(&self.inner, &[size], &self.options).remove(0);
+        if options.blur > 0.0 {
+            inner = inner.blur(options.blur);
+        }
+        if self.options.color_profile != ColorProfile::Srgb {
+            let icc_profile = self.options.color_profile.icc_bytes()?;
+            inner = convert_color_profile(inner, Some(&icc_profile), &ColorProfile::Srgb)?;
+        }
+
+        Ok(Self {
+            inner,
+            mime: self.mime,
+            tone_mapping: self.tone_mapping,
+            background: self.background,
+            color_profile: None,
+            copyright: self.copyright.clone(),
+            options: ThumbnailOptions {
+                color_profile: ColorProfile::Srgb,
+                ..self.options.clone()
+            },
+        })
+    }
+}
@@ -0,0 +1,251 @@
+//! Palette extraction by median cut refined with k-means in the oklab color space
+
+use crate::linear_light::srgb_decode;
+use crate::tone_mapping::srgb_encode;
+use crate::Thumbnail;
+use image::imageops::{self, FilterType};
+
+/// Images are sampled down to this size on their longer edge before clustering
+const SAMPLE_EDGE: u32 = 64;
+/// The number of k-means iterations refining the median cut
+const KMEANS_ITERATIONS: usize = 10;
+/// The number of colors the dominant color is picked from
+const DOMINANT_PALETTE_SIZE: usize = 5;
+
+/// A color of a palette with the share of the image it covers
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PaletteColor {
+    /// The srgb color
+    pub color: [u8; 3],
+    /// The share of the visible pixels in the range 0..=1
+    pub weight: f32,
+}
+
+/// A color in the oklab space with the weight of the pixel
+#[derive(Clone, Copy)]
+struct Sample {
+    lab: [f32; 3],
+    weight: f32,
+}
+
+impl Thumbnail {
+    /// Extracts up to `colors` colors sorted by the share of the image they cover.
+    /// Pixels are weighted by their alpha, so fully transparent images have an empty palette.
+    pub fn palette(&self, colors: usize) -> Vec<PaletteColor> {
+        let samples = self.samples();
+        let total = samples.iter().map(|sample| sample.weight).sum::<f32>();
+        if colors == 0 || total <= 0.0 {
+            return Vec::new();
+        }
+        let centroids = median_cut(&samples, colors);
+        let clusters = kmeans(&samples, centroids);
+
+        let mut palette = clusters
+            .into_iter()
+            .map(|(lab, weight)| PaletteColor {
+                color: oklab_to_srgb(lab),
+                weight: weight / total,
+            })
+            .collect::<Vec<_>>();
+        palette.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+
+        palette
+    }
+
+    /// Returns the color covering the largest share of the image
+    pub fn dominant_color(&self) -> Option<[u8; 3]> {
+        self.palette(DOMINANT_PALETTE_SIZE)
+            .first()
+            .map(|color| color.color)
+    }
+
+    /// Samples the pixels of a downscaled version without blending colors
+    fn samples(&self) -> Vec<Sample> {
+        let image = self.rgba8();
+        let (width, height) = image.dimensions();
+        let scale = (SAMPLE_EDGE as f32 / width.max(height) as f32).min(1.0);
+        let image = imageops::resize(
+            &image,
+            ((width as f32 * scale).round() as u32).max(1),
+            ((height as f32 * scale).round() as u32).max(1),
+            FilterType::Nearest,
+        );
+
+        image
+            .pixels()
+            .filter(|pixel| pixel[3] > 0)
+            .map(|pixel| Sample {
+                lab: srgb_to_oklab([pixel[0], pixel[1], pixel[2]]),
+                weight: f32::from(pixel[3]) / 255.0,
+            })
+            .collect()
+    }
+}
+
+/// Splits the samples at the weighted median of the box with the largest extent
+/// until there are enough boxes and returns their mean colors
+fn median_cut(samples: &[Sample], colors: usize) -> Vec<[f32; 3]> {
+    let mut boxes = vec![samples.to_vec()];
+
+    while boxes.len() < colors {
+        let Some((index, axis, _)) = boxes
+            .iter()
+            .enumerate()
+            .flat_map(|(index, samples)| {
+                (0..3).map(move |axis| (index, axis, extent(samples, axis)))
+            })
+            .filter(|(_, _, extent)| *extent > 0.0)
+            .max_by(|a, b| a.2.total_cmp(&b.2))
+        else {
+            break;
+        };
+        let mut samples = boxes.swap_remove(index);
+        samples.sort_by(|a, b| a.lab[axis].total_cmp(&b.lab[axis]));
+        let half = samples.iter().map(|sample| sample.weight).sum::<f32>() / 2.0;
+        let mut cumulative = 0.0;
+        let split = samples
+            .iter()
+            .position(|sample| {
+                cumulative += sample.weight;
+                cumulative >= half
+            })
+            .unwrap_or(0)
+            .clamp(1, samples.len() - 1);
+        let upper = samples.split_off(split);
+        boxes.push(samples);
+        boxes.push(upper);
+    }
+
+    boxes.iter().map(|samples| mean(samples.iter())).collect()
+}
+
+/// Moves the centroids to the mean of their closest samples and
+/// returns the centroids with the weight of their samples.
+/// Centroids that lose all their samples are moved to the samples that fit worst.
+fn kmeans(samples: &[Sample], mut centroids: Vec<[f32; 3]>) -> Vec<([f32; 3], f32)> {
+    let mut assignments = vec![0; samples.len()];
+
+    for _ in 0..KMEANS_ITERATIONS {
+        for (sample, assignment) in samples.iter().zip(assignments.iter_mut()) {
+            *assignment = closest(&centroids, sample.lab);
+        }
+        let mut moved = (0..centroids.len())
+            .map(|index| {
+                let mut members = samples
+                    .iter()
+                    .zip(&assignments)
+                    .filter(|(_, assignment)| **assignment == index)
+                    .map(|(sample, _)| sample)
+                    .peekable();
+                members.peek().is_some().then(|| mean(members))
+            })
+            .collect::<Vec<_>>();
+        if moved.iter().any(Option::is_none) {
+            reseed(&mut moved, samples, &centroids, &assignments);
+        }
+        let moved = moved.into_iter().flatten().collect::<Vec<_>>();
+        if moved == centroids {
+            break;
+        }
+        centroids = moved;
+    }
+
+    let mut weights = vec![0f32; centroids.len()];
+    for sample in samples {
+        weights[closest(&centroids, sample.lab)] += sample.weight;
+    }
+
+    centroids
+        .into_iter()
+        .zip(weights)
+        .filter(|(_, weight)| *weight > 0.0)
+        .collect()
+}
+
+/// Moves each empty cluster to a different sample, starting with the one that fits worst.
+/// Samples that already are the center of a cluster aren't used, so no colors are duplicated.
+fn reseed(
+    moved: &mut [Option<[f32; 3]>],
+    samples: &[Sample],
+    centroids: &[[f32; 3]],
+    assignments: &[usize],
+) {
+    let mut candidates = samples
+        .iter()
+        .zip(assignments)
+        .map(|(sample, assignment)| (distance(centroids[*assignment], sample.lab), sample.lab))
+        .filter(|(distance, _)| *distance > 0.0)
+        .collect::<Vec<_>>();
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let mut taken = moved.iter().flatten().copied().collect::<Vec<_>>();
+    let mut candidates = candidates.into_iter().map(|(_, lab)| lab);
+
+    for centroid in moved.iter_mut().filter(|centroid| centroid.is_none()) {
+        // clusters without a distinct sample are dropped
+        *centroid = candidates.find(|lab| !taken.contains(lab));
+        taken.extend(*centroid);
+    }
+}
+
+fn extent(samples: &[Sample], axis: usize) -> f32 {
+    let (min, max) = samples
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(min, max), sample| {
+            (min.min(sample.lab[axis]), max.max(sample.lab[axis]))
+        });
+    max - min
+}
+
+/// The weighted mean or zero for an empty set of samples
+fn mean<'a, I: Iterator<Item = &'a Sample>>(samples: I) -> [f32; 3] {
+    let (sum, weight) = samples.fold(([0f32; 3], 0f32), |(sum, weight), sample| {
+        (
+            [0, 1, 2].map(|axis| sum[axis] + sample.lab[axis] * sample.weight),
+            weight + sample.weight,
+        )
+    });
+    if weight > 0.0 {
+        sum.map(|value| value / weight)
+    } else {
+        sum
+    }
+}
+
+fn closest(centroids: &[[f32; 3]], lab: [f32; 3]) -> usize {
+    centroids
+        .iter()
+        .enumerate()
+        .min_by(|a, b| distance(*a.1, lab).total_cmp(&distance(*b.1, lab)))
+        .map_or(0, |(index, _)| index)
+}
+
+/// The squared euclidean distance which is perceptually uniform in oklab
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    (0..3).map(|axis| (a[axis] - b[axis]).powi(2)).sum()
+}
+
+fn srgb_to_oklab(color: [u8; 3]) -> [f32; 3] {
+    let [r, g, b] = color.map(|value| srgb_decode(f32::from(value) / 255.0));
+    let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+    let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+    let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+
+    [
+        0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+        1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+        0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    ]
+}
+
+fn oklab_to_srgb([lightness, a, b]: [f32; 3]) -> [u8; 3] {
+    let l = (lightness + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+    let m = (lightness - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+    let s = (lightness - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+
+    [
+        4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s,
+        -1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s,
+        -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s,
+    ]
+    .map(|value| (srgb_encode(value.clamp(0.0, 1.0)) * 255.0).round() as u8)
+}
@@ -0,0 +1,95 @@
+use crate::alpha::flatten;
+use crate::Thumbnail;
+use image::imageops::{self, FilterType};
+use image::GrayImage;
+use std::f32::consts::PI;
+use std::fmt::{Display, Formatter};
+
+/// The number of low frequencies per axis that make up the perceptual hash
+const HASH_SIZE: u32 = 8;
+/// The size the image is reduced to before the dct of the perceptual hash
+const DCT_SIZE: u32 = 32;
+
+/// A 64 bit hash of an image that changes little for visually similar images
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ImageHash(pub u64);
+
+impl ImageHash {
+    /// The number of differing bits. Hashes of the same image in different
+    /// formats or sizes usually only differ in a few bits.
+    pub const fn distance(&self, other: &Self) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+}
+
+impl Display for ImageHash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl Thumbnail {
+    /// Hashes whether each pixel of an 8x8 grayscale version is brighter than the mean
+    pub fn average_hash(&self) -> ImageHash {
+        let image = self.luma(HASH_SIZE, HASH_SIZE);
+        let mean =
+            image.pixels().map(|pixel| u32::from(pixel[0])).sum::<u32>() / (HASH_SIZE * HASH_SIZE);
+
+        hash_bits(image.pixels().map(|pixel| u32::from(pixel[0]) > mean))
+    }
+
+    /// Hashes whether the brightness increases between horizontally adjacent pixels
+    /// of a 9x8 grayscale version
+    pub fn difference_hash(&self) -> ImageHash {
+        let image = self.luma(HASH_SIZE + 1, HASH_SIZE);
+
+        hash_bits(image.rows().flat_map(|row| {
+            let row = row.map(|pixel| pixel[0]).collect::<Vec<_>>();
+            (0..HASH_SIZE as usize).map(move |x| row[x] < row[x + 1])
+        }))
+    }
+
+    /// Hashes whether the 8x8 lowest frequencies of the discrete cosine transform
+    /// of a 32x32 grayscale version are above their median
+    pub fn perceptual_hash(&self) -> ImageHash {
+        let image = self.luma(DCT_SIZE, DCT_SIZE);
+        let size = DCT_SIZE as usize;
+        let cosines = (0..HASH_SIZE as usize)
+            .map(|frequency| {
+                (0..size)
+                    .map(|x| {
+                        (PI * frequency as f32 * (2.0 * x as f32 + 1.0) / (2.0 * size as f32)).cos()
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let mut coefficients = Vec::with_capacity((HASH_SIZE * HASH_SIZE) as usize);
+        for v in &cosines {
+            for u in &cosines {
+                let coefficient = image
+                    .enumerate_pixels()
+                    .map(|(x, y, pixel)| f32::from(pixel[0]) * u[x as usize] * v[y as usize])
+                    .sum::<f32>();
+                coefficients.push(coefficient);
+            }
+        }
+        // the dc coefficient only holds the mean brightness and would skew the median
+        let mut sorted = coefficients[1..].to_vec();
+        sorted.sort_by(f32::total_cmp);
+        let median = sorted[sorted.len() / 2];
+
+        hash_bits(coefficients.iter().map(|coefficient| *coefficient > median))
+    }
+
+    /// Returns a grayscale version flattened onto the background of the thumbnail
+    fn luma(&self, width: u32, height: u32) -> GrayImage {
+        let image = flatten(&self.rgba8(), self.background);
+        let image = imageops::grayscale(&image);
+
+        imageops::resize(&image, width, height, FilterType::Triangle)
+    }
+}
+
+fn hash_bits<I: Iterator<Item = bool>>(bits: I) -> ImageHash {
+    ImageHash(bits.fold(0, |hash, bit| hash << 1 | u64::from(bit)))
+}
@@ -0,0 +1,89 @@
+//! BlurHash and ThumbHash placeholders that can be shown while the thumbnails are loading
+
+use crate::alpha::flatten;
+use crate::error::{ThumbError, ThumbResult};
+use crate::tone_mapping::to_rgba8;
+use crate::{Background, ColorProfile, Copyright, Thumbnail, ThumbnailOptions, ToneMapping};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use file_format::FileFormat;
+use image::{DynamicImage, RgbaImage};
+
+/// The size the base image is reduced to before hashing.
+/// ThumbHash doesn't accept images larger than 100 pixels on either side.
+pub(crate) const PREVIEW_SIZE: u32 = 100;
+
+/// The number of BlurHash components along the longer side of the image
+const BLURHASH_COMPONENTS: u32 = 4;
+
+/// Compact representations of an image that decode into a blurry preview
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Placeholders {
+    /// The BlurHash with four components along the longer and three along the shorter side.
+    /// Transparent images are flattened onto the default background first.
+    pub blurhash: String,
+    /// The base64 encoded ThumbHash which also keeps the alpha channel and aspect ratio
+    pub thumbhash: String,
+}
+
+impl Placeholders {
+    /// Hashes a preview that fits into [`PREVIEW_SIZE`]
+    pub(crate) fn from_preview(
+        preview: &DynamicImage,
+        tone_mapping: ToneMapping,
+        profile: &ColorProfile,
+    ) -> ThumbResult<Self> {
+        let image = to_rgba8(preview, tone_mapping, profile);
+        let (width, height) = image.dimensions();
+        let (components_x, components_y) = if width >= height {
+            (BLURHASH_COMPONENTS, BLURHASH_COMPONENTS - 1)
+        } else {
+            (BLURHASH_COMPONENTS - 1, BLURHASH_COMPONENTS)
+        };
+        let opaque = DynamicImage::ImageRgb8(flatten(&image, Background::default())).into_rgba8();
+        let blurhash = blurhash::encode(components_x, components_y, width, height, opaque.as_raw())
+            .map_err(|e| ThumbError::InvalidPlaceholder(e.to_string()))?;
+        let thumbhash =
+            thumbhash::rgba_to_thumb_hash(width as usize, height as usize, image.as_raw());
+
+        Ok(Self {
+            blurhash,
+            thumbhash: STANDARD.encode(thumbhash),
+        })
+    }
+}
+
+/// Decodes a BlurHash into a thumbnail of the given size.
+/// The hash doesn't store the aspect ratio, so the size should match the one of the source.
+pub fn blurhash_to_thumbnail(hash: &str, width: u32, height: u32) -> ThumbResult<Thumbnail> {
+    let data = blurhash::decode(hash, width, height, 1.0)
+        .map_err(|e| ThumbError::InvalidPlaceholder(e.to_string()))?;
+    let image = RgbaImage::from_raw(width, height, data).ok_or(ThumbError::Decode)?;
+
+    Ok(placeholder_thumbnail(image))
+}
+
+/// Decodes a base64 encoded ThumbHash into a thumbnail of at most 32 pixels
+/// with the aspect ratio of the source
+pub fn thumbhash_to_thumbnail(hash: &str) -> ThumbResult<Thumbnail> {
+    let hash = STANDARD
+        .decode(hash)
+        .map_err(|e| ThumbError::InvalidPlaceholder(e.to_string()))?;
+    let (width, height, data) = thumbhash::thumb_hash_to_rgba(&hash)
+        .map_err(|_| ThumbError::InvalidPlaceholder(String::from("malformed thumbhash")))?;
+    let image = RgbaImage::from_raw(width as u32, height as u32, data).ok_or(ThumbError::Decode)?;
+
+    Ok(placeholder_thumbnail(image))
+}
+
+fn placeholder_thumbnail(image: RgbaImage) -> Thumbnail {
+    Thumbnail {
+        inner: DynamicImage::ImageRgba8(image),
+        mime: FileFormat::ArbitraryBinaryData,
+        tone_mapping: ToneMapping::default(),
+        background: Background::default(),
+        color_profile: None,
+        copyright: Copyright::default(),
+        options: ThumbnailOptions::default(),
+    }
+}
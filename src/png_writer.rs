@@ -0,0 +1,308 @@
+//! Png encoding with lossless color type reduction and palette quantization
+
+use crate::encoding::{PngFilter, PngOptions, PngQuantization};
+use crate::error::ThumbResult;
+use color_quant::NeuQuant;
+use image::{ImageBuffer, Rgba, RgbaImage};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+/// The sampling factor of the quantizer where 1 is the slowest and most accurate
+const QUANTIZER_SAMPLE_FACTOR: i32 = 10;
+
+/// Pixel data stored with one of the png color types
+struct PngImage {
+    color_type: png::ColorType,
+    bit_depth: png::BitDepth,
+    data: Vec<u8>,
+    palette: Option<Vec<u8>>,
+    trns: Option<Vec<u8>>,
+}
+
+/// Writes an 8 bit image with the given tEXt chunks, reducing it to the smallest color type
+/// if enabled. Images with an icc profile aren't reduced to grayscale as the profile
+/// describes rgb data.
+pub(crate) fn write_png<W: Write>(
+    image: &RgbaImage,
+    icc_profile: Option<Vec<u8>>,
+    text: &[(&str, &str)],
+    options: &PngOptions,
+    writer: W,
+) -> ThumbResult<()> {
+    let allow_grayscale = icc_profile.is_none();
+    let lossless_palette = options
+        .reduce
+        .then(|| collect_palette(image, max_colors(options)))
+        .flatten();
+    let png_image = match (lossless_palette, &options.quantization) {
+        (Some(palette), _) if palette.len() <= 16 || !is_gray(image, allow_grayscale) => {
+            indexed_image(image, palette)
+        }
+        (None, Some(quantization)) => quantize(image, quantization),
+        _ if options.reduce => reduce(image, allow_grayscale),
+        _ => PngImage::direct(png::ColorType::Rgba, image.as_raw().clone()),
+    };
+
+    write_image(
+        png_image,
+        image.dimensions(),
+        icc_profile,
+        text,
+        options,
+        writer,
+    )
+}
+
+/// Writes a 16 bit image with the given tEXt chunks,
+/// dropping the alpha channel of opaque images if enabled
+pub(crate) fn write_png16<W: Write>(
+    image: &ImageBuffer<Rgba<u16>, Vec<u16>>,
+    icc_profile: Option<Vec<u8>>,
+    text: &[(&str, &str)],
+    options: &PngOptions,
+    writer: W,
+) -> ThumbResult<()> {
+    let opaque = options.reduce && image.pixels().all(|pixel| pixel[3] == u16::MAX);
+    let channels = if opaque { 3 } else { 4 };
+    let data = image
+        .pixels()
+        .flat_map(|pixel| pixel.0.into_iter().take(channels))
+        .flat_map(u16::to_be_bytes)
+        .collect();
+    let color_type = if opaque {
+        png::ColorType::Rgb
+    } else {
+        png::ColorType::Rgba
+    };
+    let png_image = PngImage {
+        bit_depth: png::BitDepth::Sixteen,
+        ..PngImage::direct(color_type, data)
+    };
+
+    write_image(
+        png_image,
+        image.dimensions(),
+        icc_profile,
+        text,
+        options,
+        writer,
+    )
+}
+
+impl PngImage {
+    fn direct(color_type: png::ColorType, data: Vec<u8>) -> Self {
+        Self {
+            color_type,
+            bit_depth: png::BitDepth::Eight,
+            data,
+            palette: None,
+            trns: None,
+        }
+    }
+}
+
+fn write_image<W: Write>(
+    image: PngImage,
+    (width, height): (u32, u32),
+    icc_profile: Option<Vec<u8>>,
+    text: &[(&str, &str)],
+    options: &PngOptions,
+    writer: W,
+) -> ThumbResult<()> {
+    let mut info = png::Info::with_size(width, height);
+    info.color_type = image.color_type;
+    info.bit_depth = image.bit_depth;
+    info.palette = image.palette.map(Cow::Owned);
+    info.trns = image.trns.map(Cow::Owned);
+    info.icc_profile = icc_profile.map(Cow::Owned);
+    info.uncompressed_latin1_text = text
+        .iter()
+        .map(|(keyword, text)| png::text_metadata::TEXtChunk::new(*keyword, *text))
+        .collect();
+
+    let mut encoder = png::Encoder::with_info(writer, info)?;
+    encoder.set_deflate_compression(match options.compression {
+        0 => png::DeflateCompression::NoCompression,
+        level => png::DeflateCompression::Level(level.min(9)),
+    });
+    encoder.set_filter(match options.filter {
+        PngFilter::None => png::Filter::NoFilter,
+        PngFilter::Sub => png::Filter::Sub,
+        PngFilter::Up => png::Filter::Up,
+        PngFilter::Average => png::Filter::Avg,
+        PngFilter::Paeth => png::Filter::Paeth,
+        PngFilter::Adaptive => png::Filter::Adaptive,
+    });
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&image.data)?;
+    writer.finish()?;
+
+    Ok(())
+}
+
+/// The number of colors a lossless palette may have before quantizing
+fn max_colors(options: &PngOptions) -> usize {
+    options
+        .quantization
+        .as_ref()
+        .map_or(256, |quantization| quantization.colors())
+}
+
+fn is_gray(image: &RgbaImage, allow_grayscale: bool) -> bool {
+    allow_grayscale
+        && image
+            .pixels()
+            .all(|pixel| pixel[0] == pixel[1] && pixel[1] == pixel[2])
+}
+
+/// Stores the image with the least channels that keep all information
+fn reduce(image: &RgbaImage, allow_grayscale: bool) -> PngImage {
+    let opaque = image.pixels().all(|pixel| pixel[3] == 255);
+    let (color_type, channels): (_, &[usize]) = match (is_gray(image, allow_grayscale), opaque) {
+        (true, true) => (png::ColorType::Grayscale, &[0]),
+        (true, false) => (png::ColorType::GrayscaleAlpha, &[0, 3]),
+        (false, true) => (png::ColorType::Rgb, &[0, 1, 2]),
+        (false, false) => (png::ColorType::Rgba, &[0, 1, 2, 3]),
+    };
+    let data = image
+        .pixels()
+        .flat_map(|pixel| channels.iter().map(|channel| pixel[*channel]))
+        .collect();
+
+    PngImage::direct(color_type, data)
+}
+
+/// Returns the colors of the image if there are at most `max_colors` of them
+fn collect_palette(image: &RgbaImage, max_colors: usize) -> Option<Vec<[u8; 4]>> {
+    let mut palette = Vec::new();
+    let mut seen = HashSet::new();
+    for pixel in image.pixels() {
+        if seen.insert(pixel.0) {
+            if palette.len() == max_colors {
+                return None;
+            }
+            palette.push(pixel.0);
+        }
+    }
+
+    Some(palette)
+}
+
+fn indexed_image(image: &RgbaImage, palette: Vec<[u8; 4]>) -> PngImage {
+    let lookup = palette
+        .iter()
+        .enumerate()
+        .map(|(index, color)| (*color, index as u8))
+        .collect::<HashMap<_, _>>();
+    let indices = image.pixels().map(|pixel| lookup[&pixel.0]).collect();
+
+    to_indexed(palette, indices, image.width())
+}
+
+/// Reduces the image to a palette with error diffusion dithering
+fn quantize(image: &RgbaImage, quantization: &PngQuantization) -> PngImage {
+    let quantizer = NeuQuant::new(
+        QUANTIZER_SAMPLE_FACTOR,
+        quantization.colors(),
+        image.as_raw(),
+    );
+    let palette = quantizer
+        .color_map_rgba()
+        .chunks_exact(4)
+        .map(|color| [color[0], color[1], color[2], color[3]])
+        .collect::<Vec<_>>();
+    let dithering = quantization.dithering.clamp(0.0, 1.0);
+    let width = image.width() as usize;
+    // the errors of the current and the next row with a padding pixel on both sides
+    let mut errors = vec![[0f32; 4]; width + 2];
+    let mut next_errors = vec![[0f32; 4]; width + 2];
+    let mut indices = Vec::with_capacity(image.as_raw().len() / 4);
+
+    for row in image.rows() {
+        for (x, pixel) in row.enumerate() {
+            let mut value = [0f32; 4];
+            let mut target = [0u8; 4];
+            for channel in 0..4 {
+                value[channel] = (f32::from(pixel[channel]) + errors[x + 1][channel] * dithering)
+                    .clamp(0.0, 255.0);
+                target[channel] = value[channel].round() as u8;
+            }
+            let index = quantizer.index_of(&target);
+            indices.push(index as u8);
+
+            for channel in 0..4 {
+                let error = value[channel] - f32::from(palette[index][channel]);
+                errors[x + 2][channel] += error * 7.0 / 16.0;
+                next_errors[x][channel] += error * 3.0 / 16.0;
+                next_errors[x + 1][channel] += error * 5.0 / 16.0;
+                next_errors[x + 2][channel] += error / 16.0;
+            }
+        }
+        errors = std::mem::replace(&mut next_errors, vec![[0f32; 4]; width + 2]);
+    }
+
+    to_indexed(palette, indices, image.width())
+}
+
+/// Creates an indexed image with the smallest bit depth that fits the palette.
+/// Transparent colors are moved to the front to keep the tRNS chunk short.
+fn to_indexed(palette: Vec<[u8; 4]>, indices: Vec<u8>, width: u32) -> PngImage {
+    let mut order = (0..palette.len()).collect::<Vec<_>>();
+    order.sort_by_key(|index| palette[*index][3] == 255);
+    let mut remap = vec![0u8; palette.len()];
+    for (new_index, old_index) in order.iter().enumerate() {
+        remap[*old_index] = new_index as u8;
+    }
+    let palette = order
+        .iter()
+        .map(|index| palette[*index])
+        .collect::<Vec<_>>();
+
+    let transparent = palette.iter().filter(|color| color[3] < 255).count();
+    let trns = (transparent > 0).then(|| {
+        palette[..transparent]
+            .iter()
+            .map(|color| color[3])
+            .collect()
+    });
+    let bit_depth = match palette.len() {
+        0..=2 => png::BitDepth::One,
+        3..=4 => png::BitDepth::Two,
+        5..=16 => png::BitDepth::Four,
+        _ => png::BitDepth::Eight,
+    };
+    let bits = bit_depth as usize;
+    let data = indices
+        .chunks(width as usize)
+        .flat_map(|row| {
+            row.chunks(8 / bits).map(|indices| {
+                indices
+                    .iter()
+                    .enumerate()
+                    .fold(0u8, |byte, (position, index)| {
+                        byte | remap[usize::from(*index)] << (8 - bits * (position + 1))
+                    })
+            })
+        })
+        .collect();
+
+    PngImage {
+        color_type: png::ColorType::Indexed,
+        bit_depth,
+        data,
+        palette: Some(
+            palette
+                .iter()
+                .flat_map(|color| [color[0], color[1], color[2]])
+                .collect(),
+        ),
+        trns,
+    }
+}
+
+impl PngQuantization {
+    fn colors(&self) -> usize {
+        usize::from(self.colors.clamp(2, 256))
+    }
+}
@@ -1,5 +1,30 @@
+use crate::error::ThumbError;
+use crate::freedesktop::CacheSize;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The longest edge of sizes parsed from strings, so untrusted input can't request huge images
+const MAX_PARSED_EDGE: u32 = 8192;
+/// The prefix of the freedesktop sizes, whose directory names clash with the fixed sizes
+const FREEDESKTOP_PREFIX: &str = "freedesktop-";
+
+const NAMED_SIZES: [(&str, ThumbnailSize); 5] = [
+    ("icon", ThumbnailSize::Icon),
+    ("small", ThumbnailSize::Small),
+    ("medium", ThumbnailSize::Medium),
+    ("large", ThumbnailSize::Large),
+    ("larger", ThumbnailSize::Larger),
+];
+
+const CACHE_SIZES: [CacheSize; 4] = [
+    CacheSize::Normal,
+    CacheSize::Large,
+    CacheSize::XLarge,
+    CacheSize::XXLarge,
+];
+
 /// Represents fixed sizes of a thumbnail
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum ThumbnailSize {
     Icon,
     Small,
@@ -7,9 +32,20 @@ pub enum ThumbnailSize {
     Large,
     Larger,
     Custom((u32, u32)),
+    /// The sizes of the freedesktop thumbnail managing standard.
+    /// The thumbnail keeps the aspect ratio of the source and fits into a square.
+    Freedesktop(CacheSize),
+    /// Keeps the aspect ratio of the source with the given width and an unconstrained height
+    Width(u32),
+    /// Keeps the aspect ratio of the source with the given height and an unconstrained width
+    Height(u32),
+    /// Keeps the aspect ratio of the source with the given length of the longer edge
+    MaxEdge(u32),
 }
 
 impl ThumbnailSize {
+    /// Returns the dimensions of fixed sizes and the bounds of sizes that keep the
+    /// aspect ratio of the source where unconstrained sides are `u32::MAX`
     pub const fn dimensions(&self) -> (u32, u32) {
         match self {
             Self::Icon => (64, 64),
@@ -18,6 +54,131 @@ impl ThumbnailSize {
             Self::Large => (512, 512),
             Self::Larger => (1024, 1024),
             Self::Custom(size) => *size,
+            Self::Freedesktop(size) => (size.max_edge(), size.max_edge()),
+            Self::Width(width) => (*width, u32::MAX),
+            Self::Height(height) => (u32::MAX, *height),
+            Self::MaxEdge(edge) => (*edge, *edge),
+        }
+    }
+
+    /// Returns the dimensions of the thumbnail for a source of the given dimensions
+    pub fn dimensions_for(&self, (width, height): (u32, u32)) -> (u32, u32) {
+        if !self.keeps_aspect_ratio() {
+            return self.dimensions();
+        }
+        let (width, height) = (width.max(1) as f64, height.max(1) as f64);
+        let (max_width, max_height) = self.dimensions();
+        let scale = (max_width as f64 / width).min(max_height as f64 / height);
+
+        (
+            ((width * scale).round() as u32).max(1),
+            ((height * scale).round() as u32).max(1),
+        )
+    }
+
+    /// Multiplies the size by the scale factor of high density displays.
+    /// Freedesktop sizes turn into the scaled `MaxEdge` as the standard has no larger sizes.
+    pub const fn scaled(self, factor: u32) -> Self {
+        match self {
+            Self::Freedesktop(size) => Self::MaxEdge(size.max_edge().saturating_mul(factor)),
+            Self::Width(width) => Self::Width(width.saturating_mul(factor)),
+            Self::Height(height) => Self::Height(height.saturating_mul(factor)),
+            Self::MaxEdge(edge) => Self::MaxEdge(edge.saturating_mul(factor)),
+            size => {
+                let (width, height) = size.dimensions();
+                Self::Custom((width.saturating_mul(factor), height.saturating_mul(factor)))
+            }
+        }
+    }
+
+    const fn keeps_aspect_ratio(&self) -> bool {
+        matches!(
+            self,
+            Self::Freedesktop(_) | Self::Width(_) | Self::Height(_) | Self::MaxEdge(_)
+        )
+    }
+}
+
+/// Formats the size as `medium` for the named sizes, `256x256` for custom sizes,
+/// `freedesktop-large` for the freedesktop sizes, `w320` or `h240` for a constrained
+/// width or height and `512` for the longer edge
+impl Display for ThumbnailSize {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Custom((width, height)) => write!(f, "{width}x{height}"),
+            Self::Freedesktop(size) => write!(f, "{FREEDESKTOP_PREFIX}{}", size.dir_name()),
+            Self::Width(width) => write!(f, "w{width}"),
+            Self::Height(height) => write!(f, "h{height}"),
+            Self::MaxEdge(edge) => write!(f, "{edge}"),
+            size => {
+                let (name, _) = NAMED_SIZES
+                    .into_iter()
+                    .find(|(_, named)| named == size)
+                    .expect("all fixed sizes are named");
+                write!(f, "{name}")
+            }
         }
     }
 }
+
+/// Parses the formats written by `Display` with an optional scale factor like `medium@2x`.
+/// Sizes with an edge longer than 8192 pixels are rejected.
+impl FromStr for ThumbnailSize {
+    type Err = ThumbError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ThumbError::InvalidSize(s.to_string());
+        let parse = |value: &str| value.parse::<u32>().ok().filter(|value| *value > 0);
+        let (size, factor) = match s.trim().split_once('@') {
+            Some((size, factor)) => {
+                let factor = factor
+                    .strip_suffix('x')
+                    .and_then(parse)
+                    .ok_or_else(invalid)?;
+                (size, factor)
+            }
+            None => (s.trim(), 1),
+        };
+        let size = if let Some((_, size)) = NAMED_SIZES
+            .into_iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(size))
+        {
+            size
+        } else if let Some(name) = size.strip_prefix(FREEDESKTOP_PREFIX) {
+            CACHE_SIZES
+                .into_iter()
+                .find(|cache_size| cache_size.dir_name().eq_ignore_ascii_case(name))
+                .map(Self::Freedesktop)
+                .ok_or_else(invalid)?
+        } else if let Some((width, height)) = size.split_once('x') {
+            Self::Custom((
+                parse(width).ok_or_else(invalid)?,
+                parse(height).ok_or_else(invalid)?,
+            ))
+        } else if let Some(width) = size.strip_prefix('w') {
+            Self::Width(parse(width).ok_or_else(invalid)?)
+        } else if let Some(height) = size.strip_prefix('h') {
+            Self::Height(parse(height).ok_or_else(invalid)?)
+        } else {
+            Self::MaxEdge(parse(size).ok_or_else(invalid)?)
+        };
+        let size = if factor == 1 {
+            size
+        } else {
+            size.scaled(factor)
+        };
+
+        let edge = match size {
+            Self::Width(edge) | Self::Height(edge) => edge,
+            size => {
+                let (width, height) = size.dimensions();
+                width.max(height)
+            }
+        };
+        if edge > MAX_PARSED_EDGE {
+            return Err(invalid());
+        }
+
+        Ok(size)
+    }
+}
@@ -0,0 +1,218 @@
+use crate::color_profile::{ColorProfile, TransferCurve};
+use image::{DynamicImage, ImageBuffer, Rgb32FImage, Rgba, Rgba32FImage, RgbaImage};
+
+/// Reference white of sdr content in nits as defined by ITU-R BT.2408
+const SDR_WHITE_NITS: f32 = 203.0;
+
+/// Operators used to map the unbounded values of hdr images into the displayable range.
+/// They are only applied to images with floating point samples (e.g. OpenEXR, Radiance HDR
+/// and PQ/HLG video frames) which are treated as linear light. Images without values above
+/// 1.0 are clamped instead, so the curves don't darken sdr content.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToneMapping {
+    /// Values above 1.0 are clipped
+    Clamp,
+    Reinhard,
+    /// The filmic curve fitted by Krzysztof Narkowicz
+    #[default]
+    Aces,
+    /// The filmic curve by John Hable used in Uncharted 2
+    Hable,
+}
+
+impl ToneMapping {
+    /// Maps a linear value to the range 0..=1
+    fn map(&self, value: f32) -> f32 {
+        let value = value.max(0.0);
+        let mapped = match self {
+            Self::Clamp => value,
+            Self::Reinhard => value / (1.0 + value),
+            Self::Aces => {
+                let value = value * 0.6;
+                (value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14)
+            }
+            Self::Hable => {
+                const WHITE_POINT: f32 = 11.2;
+                const EXPOSURE_BIAS: f32 = 2.0;
+                hable_curve(value * EXPOSURE_BIAS) / hable_curve(WHITE_POINT)
+            }
+        };
+
+        mapped.clamp(0.0, 1.0)
+    }
+
+    /// Tone maps a linear rgba pixel and encodes it with the transfer curve in the range 0..=1
+    fn map_pixel(&self, pixel: Rgba<f32>, transfer: &TransferCurve) -> [f32; 4] {
+        let [r, g, b, a] = pixel.0;
+        let [r, g, b] = transfer.encode([self.map(r), self.map(g), self.map(b)]);
+        [r, g, b, a.clamp(0.0, 1.0)]
+    }
+}
+
+fn hable_curve(x: f32) -> f32 {
+    const A: f32 = 0.15;
+    const B: f32 = 0.50;
+    const C: f32 = 0.10;
+    const D: f32 = 0.20;
+    const E: f32 = 0.02;
+    const F: f32 = 0.30;
+
+    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+}
+
+pub(crate) fn srgb_encode(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Returns whether the image stores hdr values. Float images store linear hdr values
+/// and 16 bit images store hdr values encoded with the transfer function if there is one.
+pub(crate) const fn is_hdr(image: &DynamicImage, transfer: Option<HdrTransfer>) -> bool {
+    match image {
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => true,
+        DynamicImage::ImageRgb16(_) | DynamicImage::ImageRgba16(_) => transfer.is_some(),
+        _ => false,
+    }
+}
+
+/// Returns whether the image stores more than 8 bits per sample
+pub(crate) fn is_high_bit_depth(image: &DynamicImage) -> bool {
+    image.color().bytes_per_pixel() / image.color().channel_count() > 1
+}
+
+/// Returns the operator for the image, which is only tone mapped if it exceeds sdr white
+fn operator_for(image: &Rgba32FImage, tone_mapping: ToneMapping) -> ToneMapping {
+    let exceeds_sdr = image
+        .pixels()
+        .any(|pixel| pixel.0[..3].iter().any(|&value| value > 1.0));
+    if exceeds_sdr {
+        tone_mapping
+    } else {
+        ToneMapping::Clamp
+    }
+}
+
+/// Converts the image to 8 bit rgba, tone mapping hdr images and encoding them with the
+/// transfer curve of the color profile they have been converted to
+pub(crate) fn to_rgba8(
+    image: &DynamicImage,
+    tone_mapping: ToneMapping,
+    profile: &ColorProfile,
+) -> RgbaImage {
+    if !is_hdr(image, None) {
+        return image.to_rgba8();
+    }
+    let image = image.to_rgba32f();
+    let tone_mapping = operator_for(&image, tone_mapping);
+    let transfer = TransferCurve::new(profile);
+    ImageBuffer::from_fn(image.width(), image.height(), |x, y| {
+        let pixel = tone_mapping.map_pixel(*image.get_pixel(x, y), &transfer);
+        Rgba(pixel.map(|value| (value * 255.0).round() as u8))
+    })
+}
+
+/// Converts the image to 16 bit rgba, tone mapping hdr images and encoding them with the
+/// transfer curve of the color profile they have been converted to
+pub(crate) fn to_rgba16(
+    image: &DynamicImage,
+    tone_mapping: ToneMapping,
+    profile: &ColorProfile,
+) -> DynamicImage {
+    if !is_hdr(image, None) {
+        return DynamicImage::ImageRgba16(image.to_rgba16());
+    }
+    let image = image.to_rgba32f();
+    let tone_mapping = operator_for(&image, tone_mapping);
+    let transfer = TransferCurve::new(profile);
+    let image = ImageBuffer::from_fn(image.width(), image.height(), |x, y| {
+        let pixel = tone_mapping.map_pixel(*image.get_pixel(x, y), &transfer);
+        Rgba(pixel.map(|value| (value * 65535.0).round() as u16))
+    });
+
+    DynamicImage::ImageRgba16(image)
+}
+
+/// Transfer functions used by hdr video and images
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum HdrTransfer {
+    /// SMPTE ST 2084
+    Pq,
+    /// ARIB STD-B67
+    Hlg,
+}
+
+/// Converts bt.2020 hdr images into linear bt.709 light where 1.0 is sdr reference white,
+/// so they can be tone mapped like float images. Other images are returned as they are.
+pub(crate) fn hdr_to_linear(image: DynamicImage, transfer: Option<HdrTransfer>) -> DynamicImage {
+    let Some(transfer) = transfer.filter(|_| is_hdr(&image, transfer)) else {
+        return image;
+    };
+    if !image.color().has_alpha() {
+        let mut image: Rgb32FImage = image.into_rgb32f();
+        for pixel in image.pixels_mut() {
+            pixel.0 = hdr_pixel_to_linear(pixel.0, transfer);
+        }
+        return DynamicImage::ImageRgb32F(image);
+    }
+    let mut image = image.into_rgba32f();
+    for pixel in image.pixels_mut() {
+        let [r, g, b, a] = pixel.0;
+        let [r, g, b] = hdr_pixel_to_linear([r, g, b], transfer);
+        pixel.0 = [r, g, b, a];
+    }
+
+    DynamicImage::ImageRgba32F(image)
+}
+
+fn hdr_pixel_to_linear(rgb: [f32; 3], transfer: HdrTransfer) -> [f32; 3] {
+    let nits = match transfer {
+        HdrTransfer::Pq => rgb.map(pq_to_nits),
+        HdrTransfer::Hlg => hlg_to_nits(rgb),
+    };
+    let [r, g, b] = nits.map(|value| value / SDR_WHITE_NITS);
+
+    // bt.2020 to bt.709 primaries
+    [
+        1.6605 * r - 0.5876 * g - 0.0728 * b,
+        -0.1246 * r + 1.1329 * g - 0.0083 * b,
+        -0.0182 * r - 0.1006 * g + 1.1187 * b,
+    ]
+}
+
+fn pq_to_nits(value: f32) -> f32 {
+    const M1: f32 = 2610.0 / 16384.0;
+    const M2: f32 = 2523.0 / 4096.0 * 128.0;
+    const C1: f32 = 3424.0 / 4096.0;
+    const C2: f32 = 2413.0 / 4096.0 * 32.0;
+    const C3: f32 = 2392.0 / 4096.0 * 32.0;
+
+    let power = value.clamp(0.0, 1.0).powf(1.0 / M2);
+    let linear = ((power - C1).max(0.0) / (C2 - C3 * power)).powf(1.0 / M1);
+
+    linear * 10000.0
+}
+
+/// Applies the inverse hlg oetf and the reference ootf for a 1000 nits display
+fn hlg_to_nits(rgb: [f32; 3]) -> [f32; 3] {
+    const A: f32 = 0.178_832_77;
+    const B: f32 = 0.284_668_92;
+    const C: f32 = 0.559_910_7;
+    const PEAK_NITS: f32 = 1000.0;
+    const SYSTEM_GAMMA: f32 = 1.2;
+
+    let [r, g, b] = rgb.map(|value| {
+        let value = value.clamp(0.0, 1.0);
+        if value <= 0.5 {
+            value * value / 3.0
+        } else {
+            (((value - C) / A).exp() + B) / 12.0
+        }
+    });
+    let luminance = 0.2627 * r + 0.6780 * g + 0.0593 * b;
+    let gain = PEAK_NITS * luminance.powf(SYSTEM_GAMMA - 1.0);
+
+    [r * gain, g * gain, b * gain]
+}
@@ -0,0 +1,84 @@
+use crate::Copyright;
+use image::metadata::Orientation;
+use image::DynamicImage;
+use std::io::{self, BufRead, Cursor, Seek, SeekFrom};
+
+/// Reads the exif data of a container format supported by kamadak-exif
+pub fn read_exif(buf: &[u8]) -> Option<exif::Exif> {
+    exif::Reader::new()
+        .read_from_container(&mut Cursor::new(buf))
+        .ok()
+}
+
+/// Returns the orientation stored in the exif data of the container
+pub fn read_orientation(buf: &[u8]) -> Option<Orientation> {
+    let value = read_exif(buf)?
+        .get_field(exif::Tag::Orientation, exif::In::PRIMARY)?
+        .value
+        .get_uint(0)?;
+
+    Orientation::from_exif(u8::try_from(value).ok()?)
+}
+
+/// Rotates and flips the image according to the exif data embedded in the container
+pub fn apply_exif_orientation(mut image: DynamicImage, buf: &[u8]) -> DynamicImage {
+    if let Some(orientation) = read_orientation(buf) {
+        image.apply_orientation(orientation);
+    }
+
+    image
+}
+
+/// Reads the copyright notices from the exif data of the container, leaving the reader
+/// at the position it started at. Missing or malformed exif data results in no notices.
+pub fn read_copyright<R: BufRead + Seek>(reader: &mut R) -> io::Result<Copyright> {
+    let start = reader.stream_position()?;
+    let exif = exif::Reader::new().read_from_container(reader).ok();
+    reader.seek(SeekFrom::Start(start))?;
+    let Some(exif) = exif else {
+        return Ok(Copyright::default());
+    };
+    let read_ascii = |tag| {
+        let field = exif.get_field(tag, exif::In::PRIMARY)?;
+        match &field.value {
+            exif::Value::Ascii(values) => values
+                .iter()
+                .map(|value| String::from_utf8_lossy(value).trim().to_string())
+                .find(|value| !value.is_empty()),
+            _ => None,
+        }
+    };
+
+    Ok(Copyright {
+        copyright: read_ascii(exif::Tag::Copyright),
+        artist: read_ascii(exif::Tag::Artist),
+    })
+}
+
+/// Encodes the copyright notices as tiff structured exif data
+pub fn write_copyright(copyright: &Copyright) -> Option<Vec<u8>> {
+    let fields = [
+        (exif::Tag::Copyright, &copyright.copyright),
+        (exif::Tag::Artist, &copyright.artist),
+    ]
+    .into_iter()
+    .filter_map(|(tag, value)| {
+        Some(exif::Field {
+            tag,
+            ifd_num: exif::In::PRIMARY,
+            value: exif::Value::Ascii(vec![value.as_ref()?.as_bytes().to_vec()]),
+        })
+    })
+    .collect::<Vec<_>>();
+    if fields.is_empty() {
+        return None;
+    }
+    let mut writer = exif::experimental::Writer::new();
+    for field in &fields {
+        writer.push_field(field);
+    }
+    let mut buf = Cursor::new(Vec::new());
+    writer.write(&mut buf, false).ok()?;
+
+    Some(buf.into_inner())
+}
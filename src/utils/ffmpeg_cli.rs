@@ -5,6 +5,7 @@ use std::io::ErrorKind;
 use std::process::{Command, Stdio};
 
 const FFMPEG: &str = "ffmpeg";
+const FFPROBE: &str = "ffprobe";
 
 /// Runs ffmpeg to retrieve a png video frame
 pub fn get_png_frame(video_file: &str, index: usize) -> ThumbResult<Vec<u8>> {
@@ -27,6 +28,50 @@ pub fn get_png_frame(video_file: &str, index: usize) -> ThumbResult<Vec<u8>> {
     ])
 }
 
+/// Runs ffmpeg to retrieve a 16 bit png video frame
+pub fn get_png16_frame(video_file: &str, index: usize) -> ThumbResult<Vec<u8>> {
+    ffmpeg([
+        "-loglevel",
+        "panic",
+        "-i",
+        video_file,
+        "-vf",
+        format!("select=eq(n\\,{index})").as_str(),
+        "-vframes",
+        "1",
+        "-pix_fmt",
+        "rgb48be",
+        "-c:v",
+        "png",
+        "-movflags",
+        "empty_moov",
+        "-f",
+        "image2pipe",
+        "pipe:1",
+    ])
+}
+
+/// Runs ffprobe to retrieve the transfer characteristics of the first video stream
+pub fn get_color_transfer(video_file: &str) -> ThumbResult<String> {
+    let output = Command::new(FFPROBE)
+        .args([
+            "-loglevel",
+            "panic",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=color_transfer",
+            "-of",
+            "default=noprint_wrappers=1:nokey=1",
+            video_file,
+        ])
+        .stdout(Stdio::piped())
+        .spawn()?
+        .wait_with_output()?;
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
 /// Runs ffmpeg to retrieve a png video frame
 pub fn get_webp_frame(video_file: &str, index: usize) -> ThumbResult<Vec<u8>> {
     ffmpeg([
@@ -48,6 +93,25 @@ pub fn get_webp_frame(video_file: &str, index: usize) -> ThumbResult<Vec<u8>> {
     ])
 }
 
+/// Runs ffmpeg to convert the first image of a file to png.
+/// The orientation is left untouched so that it can be applied from the exif data.
+pub fn get_png_image(image_file: &str) -> ThumbResult<Vec<u8>> {
+    ffmpeg([
+        "-loglevel",
+        "panic",
+        "-noautorotate",
+        "-i",
+        image_file,
+        "-frames:v",
+        "1",
+        "-c:v",
+        "png",
+        "-f",
+        "image2pipe",
+        "pipe:1",
+    ])
+}
+
 /// Runs ffmpeg with the given args
 fn ffmpeg<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(args: I) -> ThumbResult<Vec<u8>> {
     let child = Command::new(FFMPEG)
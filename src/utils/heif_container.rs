@@ -0,0 +1,146 @@
+//! Helpers for the iso-bmff container of heif based images (heic, avif)
+//! whose decoders only return the coded image
+
+use crate::utils::exif::apply_exif_orientation;
+use image::DynamicImage;
+
+/// A transformative property of an image item
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Transformation {
+    /// The `irot` property with the number of anti-clockwise quarter turns
+    Rotation(u8),
+    /// The `imir` property mirroring about the horizontal axis if set, otherwise the vertical one
+    Mirror { horizontal_axis: bool },
+}
+
+/// Returns the boxes of an iso-bmff file as type and payload
+fn read_boxes(data: &[u8]) -> Vec<([u8; 4], &[u8])> {
+    let mut boxes = Vec::new();
+    let mut offset = 0;
+
+    while offset + 8 <= data.len() {
+        let size = u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap_or_default());
+        let kind = data[offset + 4..offset + 8].try_into().unwrap_or_default();
+        let (header, size) = match size {
+            // the box extends to the end of the file
+            0 => (8, data.len() - offset),
+            1 => match data.get(offset + 8..offset + 16) {
+                Some(size) => (
+                    16,
+                    u64::from_be_bytes(size.try_into().unwrap_or_default()) as usize,
+                ),
+                None => break,
+            },
+            size => (8, size as usize),
+        };
+        let Some(payload) = data.get(offset + header..offset.saturating_add(size)) else {
+            break;
+        };
+        boxes.push((kind, payload));
+        offset += size;
+    }
+
+    boxes
+}
+
+fn find_box<'a>(data: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+    read_boxes(data)
+        .into_iter()
+        .find(|(fourcc, _)| fourcc == kind)
+        .map(|(_, payload)| payload)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u16::from_be_bytes(data.get(offset..offset + 2)?.try_into().ok()?).into())
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Reads the transformations associated with the primary item in the order they are applied
+fn read_transformations(data: &[u8]) -> Option<Vec<Transformation>> {
+    // meta, pitm and ipma are full boxes starting with the version and flags
+    let meta = find_box(data, b"meta")?.get(4..)?;
+    let pitm = find_box(meta, b"pitm")?;
+    let primary_item = match pitm.first()? {
+        0 => read_u16(pitm, 4)?,
+        _ => read_u32(pitm, 4)?,
+    };
+    let iprp = find_box(meta, b"iprp")?;
+    let properties = read_boxes(find_box(iprp, b"ipco")?);
+    let ipma = find_box(iprp, b"ipma")?;
+    let version = *ipma.first()?;
+    let large_indices = ipma.get(3)? & 1 == 1;
+    let entry_count = read_u32(ipma, 4)?;
+    let mut offset = 8;
+
+    for _ in 0..entry_count {
+        let item = if version == 0 {
+            offset += 2;
+            read_u16(ipma, offset - 2)?
+        } else {
+            offset += 4;
+            read_u32(ipma, offset - 4)?
+        };
+        let association_count = usize::from(*ipma.get(offset)?);
+        offset += 1;
+        // the associations are a 1-based property index after the essential bit
+        let indices = (0..association_count)
+            .map(|index| match large_indices {
+                true => read_u16(ipma, offset + index * 2).map(|value| value & 0x7FFF),
+                false => ipma
+                    .get(offset + index)
+                    .map(|&value| u32::from(value & 0x7F)),
+            })
+            .collect::<Option<Vec<_>>>()?;
+        offset += association_count * if large_indices { 2 } else { 1 };
+        if item != primary_item {
+            continue;
+        }
+
+        return Some(
+            indices
+                .into_iter()
+                .filter_map(|index| properties.get((index as usize).checked_sub(1)?))
+                .filter_map(|(kind, payload)| match kind {
+                    b"irot" => Some(Transformation::Rotation(payload.first()? & 0x03)),
+                    b"imir" => Some(Transformation::Mirror {
+                        horizontal_axis: payload.first()? & 0x01 == 1,
+                    }),
+                    _ => None,
+                })
+                .collect(),
+        );
+    }
+
+    Some(Vec::new())
+}
+
+/// Rotates and mirrors the image according to the `irot` and `imir` properties of the
+/// primary item. Images without these properties fall back to the exif orientation.
+pub fn apply_transformations(mut image: DynamicImage, data: &[u8]) -> DynamicImage {
+    let transformations = read_transformations(data).unwrap_or_default();
+    if transformations.is_empty() {
+        return apply_exif_orientation(image, data);
+    }
+
+    for transformation in transformations {
+        image = match transformation {
+            Transformation::Rotation(1) => image.rotate270(),
+            Transformation::Rotation(2) => image.rotate180(),
+            Transformation::Rotation(3) => image.rotate90(),
+            Transformation::Rotation(_) => image,
+            Transformation::Mirror {
+                horizontal_axis: true,
+            } => image.flipv(),
+            Transformation::Mirror {
+                horizontal_axis: false,
+            } => image.fliph(),
+        };
+    }
+
+    image
+}
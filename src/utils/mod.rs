@@ -1 +1,5 @@
+pub mod exif;
 pub mod ffmpeg_cli;
+pub mod heif_container;
+pub mod pdftoppm_cli;
+pub mod webp_container;
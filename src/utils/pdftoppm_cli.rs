@@ -0,0 +1,71 @@
+use crate::error::ThumbError;
+use crate::ThumbResult;
+use std::ffi::OsStr;
+use std::io::ErrorKind;
+use std::process::{Command, Stdio};
+
+const PDFTOPPM: &str = "pdftoppm";
+
+/// Exit code pdftoppm uses when the document can't be opened
+const ERROR_OPEN_FILE: i32 = 1;
+/// Exit code xpdf's pdftoppm uses for documents that require a password
+const ERROR_PERMISSIONS: i32 = 3;
+/// Message poppler's pdftoppm prints before exiting with `ERROR_OPEN_FILE`
+/// for documents that require a password
+const INCORRECT_PASSWORD: &str = "Incorrect password";
+
+/// Runs pdftoppm to render a single pdf page as png.
+/// The page index starts at 1. If a scale is given, the longer side of the page
+/// is rendered with that many pixels.
+pub fn get_png_page(pdf_file: &str, page: usize, scale_to: Option<u32>) -> ThumbResult<Vec<u8>> {
+    let page = page.to_string();
+    let mut args = vec![
+        "-png",
+        "-singlefile",
+        "-f",
+        page.as_str(),
+        "-l",
+        page.as_str(),
+    ];
+    let scale_to = scale_to.map(|s| s.to_string());
+
+    if let Some(scale_to) = &scale_to {
+        args.extend(["-scale-to", scale_to.as_str()]);
+    }
+    args.push(pdf_file);
+
+    pdftoppm(args)
+}
+
+/// Runs pdftoppm with the given args
+fn pdftoppm<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(args: I) -> ThumbResult<Vec<u8>> {
+    let child = Command::new(PDFTOPPM)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let output = child.wait_with_output()?;
+    if output.status.success() && !output.stdout.is_empty() {
+        return Ok(output.stdout);
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr[..]).to_string();
+    match output.status.code() {
+        Some(ERROR_PERMISSIONS) => Err(ThumbError::EncryptedPdf),
+        _ if stderr.contains(INCORRECT_PASSWORD) => Err(ThumbError::EncryptedPdf),
+        Some(ERROR_OPEN_FILE) => Err(ThumbError::MalformedPdf),
+        _ => Err(ThumbError::Pdftoppm(stderr)),
+    }
+}
+
+pub fn is_pdftoppm_installed() -> bool {
+    match Command::new(PDFTOPPM)
+        .arg("-v")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(_) => true,
+        Err(e) => !matches!(e.kind(), ErrorKind::NotFound),
+    }
+}
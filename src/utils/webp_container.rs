@@ -0,0 +1,86 @@
+//! Helpers for the riff container of webp images which libwebp's simple api doesn't expose
+
+#[cfg(feature = "webp")]
+const VP8X_ICC_FLAG: u8 = 0x20;
+#[cfg(feature = "webp")]
+const VP8X_ALPHA_FLAG: u8 = 0x10;
+
+/// Returns the chunks of a webp file as fourcc and payload
+fn read_chunks(data: &[u8]) -> Option<Vec<([u8; 4], &[u8])>> {
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WEBP" {
+        return None;
+    }
+    let mut chunks = Vec::new();
+    let mut offset = 12;
+
+    while offset + 8 <= data.len() {
+        let fourcc = data[offset..offset + 4].try_into().ok()?;
+        let size = u32::from_le_bytes(data[offset + 4..offset + 8].try_into().ok()?) as usize;
+        let payload = data.get(offset + 8..offset + 8 + size)?;
+        chunks.push((fourcc, payload));
+        offset += 8 + size + size % 2;
+    }
+
+    Some(chunks)
+}
+
+/// Reads the icc profile stored in the ICCP chunk of a webp file
+pub fn read_icc_profile(data: &[u8]) -> Option<Vec<u8>> {
+    read_chunks(data)?
+        .into_iter()
+        .find(|(fourcc, _)| fourcc == b"ICCP")
+        .map(|(_, payload)| payload.to_vec())
+}
+
+/// Stores the icc profile in a webp file, converting it to the extended format if necessary
+#[cfg(feature = "webp")]
+pub fn embed_icc_profile(data: &[u8], icc_profile: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let Some(chunks) = read_chunks(data) else {
+        return data.to_vec();
+    };
+    let has_alpha = chunks.iter().any(|(fourcc, payload)| match fourcc {
+        b"VP8X" => payload
+            .first()
+            .is_some_and(|flags| flags & VP8X_ALPHA_FLAG != 0),
+        b"ALPH" => true,
+        // the alpha_is_used bit follows the signature byte and the 28 bits of dimensions
+        b"VP8L" => payload.get(4).is_some_and(|byte| byte & 0x10 != 0),
+        _ => false,
+    });
+    let mut flags = VP8X_ICC_FLAG;
+    if has_alpha {
+        flags |= VP8X_ALPHA_FLAG;
+    }
+    if let Some((_, payload)) = chunks.iter().find(|(fourcc, _)| fourcc == b"VP8X") {
+        flags |= payload.first().copied().unwrap_or_default();
+    }
+
+    let mut vp8x = vec![flags, 0, 0, 0];
+    vp8x.extend_from_slice(&(width.saturating_sub(1)).to_le_bytes()[..3]);
+    vp8x.extend_from_slice(&(height.saturating_sub(1)).to_le_bytes()[..3]);
+
+    let mut body = b"WEBP".to_vec();
+    write_chunk(&mut body, b"VP8X", &vp8x);
+    write_chunk(&mut body, b"ICCP", icc_profile);
+    for (fourcc, payload) in chunks {
+        if &fourcc != b"VP8X" && &fourcc != b"ICCP" {
+            write_chunk(&mut body, &fourcc, payload);
+        }
+    }
+
+    let mut webp = b"RIFF".to_vec();
+    webp.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    webp.extend_from_slice(&body);
+
+    webp
+}
+
+#[cfg(feature = "webp")]
+fn write_chunk(buf: &mut Vec<u8>, fourcc: &[u8; 4], payload: &[u8]) {
+    buf.extend_from_slice(fourcc);
+    buf.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    buf.extend_from_slice(payload);
+    if payload.len() % 2 == 1 {
+        buf.push(0);
+    }
+}
@@ -0,0 +1,189 @@
+use file_format::FileFormat;
+use image::RgbImage;
+use std::io::Cursor;
+use thumbnailer::{create_thumbnails_with_options, Adjustment, ThumbnailOptions, ThumbnailSize};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+#[test]
+fn it_converts_to_grayscale() {
+    let image = create_image(vec![Adjustment::Grayscale]);
+
+    assert!(image.pixels().all(|pixel| {
+        let [r, g, b] = pixel.0;
+        r == g && g == b
+    }));
+}
+
+#[test]
+fn it_desaturates_to_grayscale() {
+    let grayscale = create_image(vec![Adjustment::Grayscale]);
+    let desaturated = create_image(vec![Adjustment::Saturation(0.0)]);
+
+    assert_eq!(grayscale, desaturated);
+}
+
+#[test]
+fn it_adjusts_brightness() {
+    let original = mean(&create_image(Vec::new()));
+    let brighter = mean(&create_image(vec![Adjustment::Brightness(0.2)]));
+    let darker = mean(&create_image(vec![Adjustment::Brightness(-0.2)]));
+
+    assert!(
+        brighter > original + 20.0,
+        "{brighter} isn't brighter than {original}"
+    );
+    assert!(
+        darker < original - 5.0,
+        "{darker} isn't darker than {original}"
+    );
+}
+
+#[test]
+fn it_removes_all_contrast() {
+    let image = create_image(vec![Adjustment::Contrast(0.0)]);
+
+    assert!(image
+        .pixels()
+        .all(|pixel| pixel.0.iter().all(|value| value.abs_diff(128) <= 1)));
+}
+
+#[test]
+fn it_sharpens_edges() {
+    let original = gradient(&create_image(Vec::new()));
+    let sharpened = gradient(&create_image(vec![Adjustment::UnsharpMask {
+        radius: 1.0,
+        amount: 1.0,
+        threshold: 0,
+    }]));
+
+    assert!(
+        sharpened > original,
+        "{sharpened} isn't sharper than {original}"
+    );
+}
+
+#[test]
+fn it_skips_sharpening_below_the_threshold() {
+    let original = create_image(Vec::new());
+    let sharpened = create_image(vec![Adjustment::UnsharpMask {
+        radius: 1.0,
+        amount: 1.0,
+        threshold: 255,
+    }]);
+
+    assert_eq!(original, sharpened);
+}
+
+#[test]
+fn it_sharpens_transparent_images_without_halos() {
+    let image = image::RgbaImage::from_fn(32, 32, |x, _| {
+        if x < 16 {
+            image::Rgba([128, 128, 128, 255])
+        } else {
+            image::Rgba([255, 255, 255, 0])
+        }
+    });
+    let mut png = Cursor::new(Vec::new());
+    image.write_to(&mut png, image::ImageFormat::Png).unwrap();
+    png.set_position(0);
+    let options = ThumbnailOptions {
+        adjustments: vec![Adjustment::UnsharpMask {
+            radius: 2.0,
+            amount: 1.0,
+            threshold: 0,
+        }],
+        ..Default::default()
+    };
+    let thumbnail = create_thumbnails_with_options(
+        png,
+        FileFormat::PortableNetworkGraphics,
+        [ThumbnailSize::Custom((32, 32))],
+        &options,
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+    let sharpened = image::load_from_memory(buf.get_ref()).unwrap().into_rgba8();
+
+    assert!(sharpened
+        .pixels()
+        .filter(|pixel| pixel[3] == 255)
+        .all(|pixel| pixel.0[..3].iter().all(|value| value.abs_diff(128) <= 2)));
+}
+
+#[test]
+fn it_applies_adjustments_to_every_thumbnail() {
+    let options = ThumbnailOptions {
+        adjustments: vec![Adjustment::Grayscale],
+        ..Default::default()
+    };
+    let thumbnails = create_thumbnails_with_options(
+        Cursor::new(PNG_BYTES),
+        FileFormat::PortableNetworkGraphics,
+        [
+            ThumbnailSize::Icon,
+            ThumbnailSize::Small,
+            ThumbnailSize::Medium,
+        ],
+        &options,
+    )
+    .unwrap();
+
+    assert_eq!(thumbnails.len(), 3);
+    for thumbnail in thumbnails {
+        let mut buf = Cursor::new(Vec::new());
+        thumbnail.write_png(&mut buf).unwrap();
+        let image = image::load_from_memory(buf.get_ref()).unwrap().into_rgb8();
+        assert!(image
+            .pixels()
+            .all(|pixel| pixel[0] == pixel[1] && pixel[1] == pixel[2]));
+    }
+}
+
+fn mean(image: &RgbImage) -> f64 {
+    let sum: u64 = image.as_raw().iter().map(|value| u64::from(*value)).sum();
+    sum as f64 / image.as_raw().len() as f64
+}
+
+/// Sums up the differences of horizontally neighbouring pixels
+fn gradient(image: &RgbImage) -> u64 {
+    image
+        .rows()
+        .flat_map(|row| {
+            let row = row.collect::<Vec<_>>();
+            row.windows(2)
+                .map(|pair| {
+                    pair[0]
+                        .0
+                        .iter()
+                        .zip(pair[1].0)
+                        .map(|(a, b)| u64::from(a.abs_diff(b)))
+                        .sum::<u64>()
+                })
+                .collect::<Vec<_>>()
+        })
+        .sum()
+}
+
+fn create_image(adjustments: Vec<Adjustment>) -> RgbImage {
+    let options = ThumbnailOptions {
+        adjustments,
+        ..Default::default()
+    };
+    let thumbnail = create_thumbnails_with_options(
+        Cursor::new(PNG_BYTES),
+        FileFormat::PortableNetworkGraphics,
+        [ThumbnailSize::Custom((64, 64))],
+        &options,
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+
+    image::load_from_memory(buf.get_ref()).unwrap().into_rgb8()
+}
@@ -0,0 +1,96 @@
+use file_format::FileFormat;
+use std::io::Cursor;
+use thumbnailer::{
+    create_thumbnails_with_options, Background, FilterType, Thumbnail, ThumbnailOptions,
+    ThumbnailSize,
+};
+
+// opaque green stripes on fully transparent red
+const TRANSPARENT_BYTES: &[u8] = include_bytes!("assets/test_transparent.png");
+
+#[test]
+fn it_doesnt_bleed_transparent_colors_into_edges() {
+    for linear_light in [false, true] {
+        for filter in [
+            FilterType::Triangle,
+            FilterType::CatmullRom,
+            FilterType::Gaussian,
+            FilterType::Lanczos3,
+        ] {
+            let mut buf = Cursor::new(Vec::new());
+            create_thumbnail(filter.clone(), linear_light)
+                .write_png(&mut buf)
+                .unwrap();
+            let image = image::load_from_memory(buf.get_ref()).unwrap().into_rgba8();
+
+            for pixel in image.pixels().filter(|pixel| pixel[3] > 0) {
+                assert!(
+                    pixel[0] < 8,
+                    "{filter:?} (linear light {linear_light}) produced a fringe {pixel:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn it_flattens_transparency_onto_white_by_default() {
+    let image = write_jpeg(create_thumbnail(FilterType::Nearest, false));
+
+    assert_color(image.get_pixel(2, 8).0, [0, 255, 0]);
+    assert_color(image.get_pixel(6, 8).0, [255, 255, 255]);
+}
+
+#[test]
+fn it_flattens_transparency_onto_the_background_color() {
+    let mut thumbnail = create_thumbnail(FilterType::Nearest, false);
+    thumbnail.set_background(Background::Color([255, 0, 255]));
+    let image = write_jpeg(thumbnail);
+
+    assert_color(image.get_pixel(6, 8).0, [255, 0, 255]);
+}
+
+#[test]
+fn it_flattens_transparency_onto_a_checkerboard() {
+    let mut thumbnail = create_thumbnail(FilterType::Nearest, false);
+    thumbnail.set_background(Background::Checkerboard(2));
+    let image = write_jpeg(thumbnail);
+
+    // the transparent stripe spans x 4..8
+    assert_color(image.get_pixel(4, 0).0, [255, 255, 255]);
+    assert_color(image.get_pixel(6, 0).0, [204, 204, 204]);
+    assert_color(image.get_pixel(6, 2).0, [255, 255, 255]);
+}
+
+fn assert_color(color: [u8; 3], expected: [u8; 3]) {
+    for (channel, expected) in color.into_iter().zip(expected) {
+        assert!(
+            channel.abs_diff(expected) <= 12,
+            "expected {expected:?} but got {color:?}"
+        );
+    }
+}
+
+fn write_jpeg(thumbnail: Thumbnail) -> image::RgbImage {
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_jpeg(&mut buf, 100).unwrap();
+
+    image::load_from_memory(buf.get_ref()).unwrap().into_rgb8()
+}
+
+fn create_thumbnail(filter: FilterType, linear_light: bool) -> Thumbnail {
+    let options = ThumbnailOptions {
+        filter,
+        linear_light,
+        ..Default::default()
+    };
+    create_thumbnails_with_options(
+        Cursor::new(TRANSPARENT_BYTES),
+        FileFormat::PortableNetworkGraphics,
+        [ThumbnailSize::Custom((32, 32))],
+        &options,
+    )
+    .unwrap()
+    .pop()
+    .unwrap()
+}
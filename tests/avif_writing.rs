@@ -0,0 +1,144 @@
+#![cfg(feature = "avif")]
+
+use file_format::FileFormat;
+use image::RgbaImage;
+use std::io::Cursor;
+use thumbnailer::error::ThumbError;
+use thumbnailer::{
+    create_thumbnails, create_thumbnails_with_options, AvifOptions, ColorProfile, OutputFormat,
+    Thumbnail, ThumbnailOptions, ThumbnailSize,
+};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+// green stripes on a fully transparent background
+const TRANSPARENT_PNG_BYTES: &[u8] = include_bytes!("assets/test_transparent.png");
+
+#[test]
+fn it_writes_avif_images() {
+    let thumbnail = create_thumbnail(PNG_BYTES);
+    let avif = write_avif(&thumbnail, &AvifOptions::default());
+    let image = decode(&avif);
+
+    assert_eq!(image.dimensions(), (64, 64));
+    assert!(mean_difference(&image, &to_rgba8(&thumbnail)) < 8.0);
+}
+
+#[test]
+fn it_writes_smaller_files_with_lower_quality() {
+    let thumbnail = create_thumbnail(PNG_BYTES);
+    let high = write_avif(
+        &thumbnail,
+        &AvifOptions {
+            quality: 90.0,
+            ..Default::default()
+        },
+    );
+    let low = write_avif(
+        &thumbnail,
+        &AvifOptions {
+            quality: 20.0,
+            ..Default::default()
+        },
+    );
+
+    assert!(
+        low.len() < high.len(),
+        "quality 20 produced {} bytes and quality 90 {} bytes",
+        low.len(),
+        high.len()
+    );
+}
+
+#[test]
+fn it_keeps_the_alpha_channel() {
+    let thumbnail = create_thumbnail(TRANSPARENT_PNG_BYTES);
+    let original = to_rgba8(&thumbnail);
+    let options = AvifOptions {
+        alpha_quality: 100.0,
+        speed: 10,
+        ..Default::default()
+    };
+    let image = decode(&write_avif(&thumbnail, &options));
+
+    for (pixel, expected) in image.pixels().zip(original.pixels()) {
+        assert!(
+            pixel[3].abs_diff(expected[3]) <= 8,
+            "expected alpha {} but got {}",
+            expected[3],
+            pixel[3]
+        );
+    }
+}
+
+#[test]
+fn it_rejects_thumbnails_with_other_color_profiles() {
+    for embed_color_profile in [false, true] {
+        let options = ThumbnailOptions {
+            color_profile: ColorProfile::DisplayP3,
+            embed_color_profile,
+            ..Default::default()
+        };
+        let thumbnail = create_thumbnails_with_options(
+            Cursor::new(PNG_BYTES),
+            FileFormat::PortableNetworkGraphics,
+            [ThumbnailSize::Icon],
+            &options,
+        )
+        .unwrap()
+        .pop()
+        .unwrap();
+        let result = thumbnail.encode(&OutputFormat::Avif(AvifOptions::default()), &mut Vec::new());
+
+        assert!(matches!(result, Err(ThumbError::Avif(_))));
+    }
+}
+
+fn mean_difference(a: &RgbaImage, b: &RgbaImage) -> f64 {
+    let sum: u64 = a
+        .pixels()
+        .zip(b.pixels())
+        .filter(|(a, _)| a[3] > 0)
+        .flat_map(|(a, b)| a.0.into_iter().zip(b.0))
+        .map(|(a, b)| u64::from(a.abs_diff(b)))
+        .sum();
+    sum as f64 / a.as_raw().len() as f64
+}
+
+fn write_avif(thumbnail: &Thumbnail, options: &AvifOptions) -> Vec<u8> {
+    let mut buf = Vec::new();
+    thumbnail.clone().write_avif(&mut buf, options).unwrap();
+
+    buf
+}
+
+/// Decodes the avif image with the crate's own reader
+fn decode(bytes: &[u8]) -> RgbaImage {
+    let thumbnail = create_thumbnails(
+        Cursor::new(bytes),
+        FileFormat::Av1ImageFileFormat,
+        [ThumbnailSize::Custom((64, 64))],
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+
+    to_rgba8(&thumbnail)
+}
+
+fn to_rgba8(thumbnail: &Thumbnail) -> RgbaImage {
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.clone().write_png(&mut buf).unwrap();
+
+    image::load_from_memory(buf.get_ref()).unwrap().into_rgba8()
+}
+
+fn create_thumbnail(bytes: &[u8]) -> Thumbnail {
+    create_thumbnails(
+        Cursor::new(bytes),
+        FileFormat::PortableNetworkGraphics,
+        [ThumbnailSize::Custom((64, 64))],
+    )
+    .unwrap()
+    .pop()
+    .unwrap()
+}
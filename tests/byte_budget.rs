@@ -0,0 +1,159 @@
+use file_format::FileFormat;
+use std::io::Cursor;
+use thumbnailer::error::ThumbError;
+use thumbnailer::{
+    create_thumbnails, create_thumbnails_with_options, ByteBudget, FilterType, JpegOptions,
+    OutputFormat, PngOptions, Thumbnail, ThumbnailOptions, ThumbnailSize,
+};
+
+const JPG_BYTES: &[u8] = include_bytes!("assets/test.jpg");
+
+#[test]
+fn it_encodes_jpegs_with_the_highest_quality_within_the_budget() {
+    let thumbnail = create_thumbnail();
+    let format = OutputFormat::Jpeg(JpegOptions {
+        quality: 100,
+        ..Default::default()
+    });
+    let max_bytes = encoded_len(&thumbnail, 100) * 2 / 3;
+    let image = thumbnail
+        .encode_within_budget(&format, &ByteBudget::new(max_bytes))
+        .unwrap();
+    let quality = image.quality.unwrap();
+
+    assert!(image.data.len() <= max_bytes);
+    assert!(encoded_len(&thumbnail, quality + 1) > max_bytes);
+    assert_eq!((image.width, image.height), thumbnail.size());
+    assert!(image::load_from_memory(&image.data).is_ok());
+}
+
+#[test]
+fn it_uses_the_quality_of_the_format_as_the_upper_limit() {
+    let thumbnail = create_thumbnail();
+    let format = OutputFormat::Jpeg(JpegOptions {
+        quality: 75,
+        ..Default::default()
+    });
+    let image = thumbnail
+        .encode_within_budget(&format, &ByteBudget::new(usize::MAX))
+        .unwrap();
+
+    assert_eq!(image.quality, Some(75));
+}
+
+#[test]
+fn it_fails_when_the_budget_is_too_small() {
+    let thumbnail = create_thumbnail();
+    let format = OutputFormat::Jpeg(JpegOptions::default());
+    let result = thumbnail.encode_within_budget(&format, &ByteBudget::new(100));
+
+    assert!(matches!(result, Err(ThumbError::ExceedsByteBudget(100))));
+}
+
+#[test]
+fn it_downscales_images_that_dont_fit() {
+    let thumbnail = create_thumbnail();
+    let budget = ByteBudget {
+        downscale: true,
+        ..ByteBudget::new(2000)
+    };
+
+    for format in [
+        OutputFormat::Png(PngOptions::default()),
+        OutputFormat::Jpeg(JpegOptions::default()),
+    ] {
+        let image = thumbnail.encode_within_budget(&format, &budget).unwrap();
+        let decoded = image::load_from_memory(&image.data).unwrap();
+
+        assert!(image.data.len() <= 2000, "{format:?} exceeds the budget");
+        assert!(image.width < thumbnail.size().0);
+        assert_eq!(
+            (decoded.width(), decoded.height()),
+            (image.width, image.height)
+        );
+    }
+}
+
+#[test]
+fn it_downscales_with_the_options_of_the_thumbnail() {
+    // pseudo random pixels that don't compress well
+    let noise = image::RgbImage::from_fn(64, 64, |x, y| {
+        if (x * 7919 + y * 104_729).wrapping_mul(2_654_435_761) & (1 << 20) == 0 {
+            image::Rgb([255, 0, 0])
+        } else {
+            image::Rgb([0, 0, 255])
+        }
+    });
+    let mut png = Cursor::new(Vec::new());
+    noise.write_to(&mut png, image::ImageFormat::Png).unwrap();
+    png.set_position(0);
+    let options = ThumbnailOptions {
+        filter: FilterType::Nearest,
+        ..Default::default()
+    };
+    let thumbnail = create_thumbnails_with_options(
+        png,
+        FileFormat::PortableNetworkGraphics,
+        [ThumbnailSize::Custom((64, 64))],
+        &options,
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+    let budget = ByteBudget {
+        downscale: true,
+        ..ByteBudget::new(150)
+    };
+    let image = thumbnail
+        .encode_within_budget(&OutputFormat::Png(PngOptions::default()), &budget)
+        .unwrap();
+    let decoded = image::load_from_memory(&image.data).unwrap().into_rgb8();
+
+    assert!(image.width < 64);
+    // the nearest filter doesn't blend the colors
+    assert!(decoded
+        .pixels()
+        .all(|pixel| pixel.0 == [255, 0, 0] || pixel.0 == [0, 0, 255]));
+}
+
+#[cfg(feature = "webp")]
+#[test]
+fn it_encodes_webp_within_the_budget() {
+    let thumbnail = create_thumbnail();
+    let format = OutputFormat::Webp(thumbnailer::WebpOptions {
+        quality: 100.0,
+        ..Default::default()
+    });
+    let image = thumbnail
+        .encode_within_budget(&format, &ByteBudget::new(4000))
+        .unwrap();
+
+    assert!(image.data.len() <= 4000);
+    assert!(image.quality.unwrap() < 100);
+}
+
+fn encoded_len(thumbnail: &Thumbnail, quality: u8) -> usize {
+    let mut buf = Vec::new();
+    thumbnail
+        .encode(
+            &OutputFormat::Jpeg(JpegOptions {
+                quality,
+                ..Default::default()
+            }),
+            &mut buf,
+        )
+        .unwrap();
+
+    buf.len()
+}
+
+fn create_thumbnail() -> Thumbnail {
+    create_thumbnails(
+        Cursor::new(JPG_BYTES),
+        FileFormat::JointPhotographicExpertsGroup,
+        [ThumbnailSize::Medium],
+    )
+    .unwrap()
+    .pop()
+    .unwrap()
+}
@@ -0,0 +1,304 @@
+use file_format::FileFormat;
+use image::codecs::png::{PngDecoder, PngEncoder};
+use image::{ImageDecoder, ImageEncoder, RgbImage};
+use std::io::Cursor;
+use thumbnailer::error::ThumbError;
+use thumbnailer::{
+    create_thumbnails_with_options, ColorProfile, Thumbnail, ThumbnailOptions, ThumbnailSize,
+};
+
+// all assets contain the srgb color (40, 180, 90) stored as display p3 values (88, 177, 100)
+const P3_PNG_BYTES: &[u8] = include_bytes!("assets/test_p3.png");
+const P3_JPG_BYTES: &[u8] = include_bytes!("assets/test_p3.jpg");
+const P3_WEBP_BYTES: &[u8] = include_bytes!("assets/test_p3.webp");
+// the embedded cmyk profile only darkens to mid grey with full black ink
+const CMYK_JPG_BYTES: &[u8] = include_bytes!("assets/test_cmyk.jpg");
+const YCCK_JPG_BYTES: &[u8] = include_bytes!("assets/test_ycck.jpg");
+
+const SRGB_COLOR: [u8; 3] = [40, 180, 90];
+const P3_COLOR: [u8; 3] = [88, 177, 100];
+
+#[test]
+fn it_converts_png_to_srgb() {
+    let image = create_image(
+        P3_PNG_BYTES,
+        FileFormat::PortableNetworkGraphics,
+        &ThumbnailOptions::default(),
+    );
+    assert_color(&image, 8, 8, SRGB_COLOR, 2);
+}
+
+#[test]
+fn it_converts_jpeg_to_srgb() {
+    let image = create_image(
+        P3_JPG_BYTES,
+        FileFormat::JointPhotographicExpertsGroup,
+        &ThumbnailOptions::default(),
+    );
+    assert_color(&image, 8, 8, SRGB_COLOR, 4);
+}
+
+#[test]
+fn it_converts_webp_to_srgb() {
+    let image = create_image(
+        P3_WEBP_BYTES,
+        FileFormat::Webp,
+        &ThumbnailOptions::default(),
+    );
+    assert_color(&image, 8, 8, SRGB_COLOR, 2);
+}
+
+#[cfg(feature = "tiff")]
+#[test]
+fn it_converts_tiff_to_srgb() {
+    const P3_TIFF_BYTES: &[u8] = include_bytes!("assets/test_p3.tiff");
+
+    let image = create_image(
+        P3_TIFF_BYTES,
+        FileFormat::TagImageFileFormat,
+        &ThumbnailOptions::default(),
+    );
+    assert_color(&image, 8, 8, SRGB_COLOR, 2);
+}
+
+#[cfg(feature = "jxl")]
+#[test]
+fn it_converts_jxl_to_srgb() {
+    const P3_JXL_BYTES: &[u8] = include_bytes!("assets/test_p3.jxl");
+
+    let image = create_image(
+        P3_JXL_BYTES,
+        FileFormat::JpegXl,
+        &ThumbnailOptions::default(),
+    );
+    assert_color(&image, 8, 8, SRGB_COLOR, 2);
+}
+
+/// The fixture signals the display p3 primaries in its av1 sequence header
+#[cfg(feature = "avif")]
+#[test]
+fn it_converts_avif_to_srgb() {
+    const P3_AVIF_BYTES: &[u8] = include_bytes!("assets/test_p3.avif");
+
+    let image = create_image(
+        P3_AVIF_BYTES,
+        FileFormat::Av1ImageFileFormat,
+        &ThumbnailOptions::default(),
+    );
+    assert_color(&image, 8, 8, SRGB_COLOR, 2);
+}
+
+#[test]
+fn it_converts_to_the_target_profile() {
+    let options = ThumbnailOptions {
+        color_profile: ColorProfile::DisplayP3,
+        ..Default::default()
+    };
+    let image = create_image(P3_PNG_BYTES, FileFormat::PortableNetworkGraphics, &options);
+    assert_color(&image, 8, 8, P3_COLOR, 2);
+}
+
+#[cfg(feature = "openexr")]
+#[test]
+fn it_converts_float_images_like_8_bit_images() {
+    let options = ThumbnailOptions {
+        color_profile: ColorProfile::DisplayP3,
+        ..Default::default()
+    };
+    let float_image = |options: &ThumbnailOptions| {
+        let mut thumbnail = create_thumbnail(
+            include_bytes!("assets/test.exr"),
+            FileFormat::Openexr,
+            options,
+        );
+        thumbnail.set_tone_mapping(thumbnailer::ToneMapping::Clamp);
+        let mut buf = Cursor::new(Vec::new());
+        thumbnail.write_png(&mut buf).unwrap();
+        image::load_from_memory(buf.get_ref()).unwrap().into_rgb8()
+    };
+    let srgb = float_image(&ThumbnailOptions::default());
+    let p3 = float_image(&options);
+    // an 8 bit srgb image with the color of the converted float image
+    let mut png = Cursor::new(Vec::new());
+    RgbImage::from_pixel(32, 16, *srgb.get_pixel(16, 8))
+        .write_to(&mut png, image::ImageFormat::Png)
+        .unwrap();
+    let expected = create_image(png.get_ref(), FileFormat::PortableNetworkGraphics, &options);
+
+    assert_ne!(srgb.get_pixel(16, 8), p3.get_pixel(16, 8));
+    assert_color(&p3, 16, 8, expected.get_pixel(16, 8).0, 2);
+}
+
+/// Linear 0.002 is encoded as 7 by the srgb curve and as 15 by the 2.2 gamma of adobe rgb
+#[cfg(feature = "hdr")]
+#[test]
+fn it_encodes_float_images_with_the_transfer_curve_of_the_target() {
+    let mut hdr = Vec::new();
+    image::codecs::hdr::HdrEncoder::new(&mut hdr)
+        .encode(&[image::Rgb([0.002; 3]); 32 * 16], 32, 16)
+        .unwrap();
+    let options = ThumbnailOptions {
+        color_profile: ColorProfile::AdobeRgb,
+        ..Default::default()
+    };
+    let image = create_image(&hdr, FileFormat::RadianceHdr, &options);
+
+    assert_color(&image, 16, 8, [15; 3], 1);
+}
+
+/// Returns a profile that can be parsed but can't be used to convert colors
+/// as it has neither colorants nor lookup tables
+fn unusable_profile() -> Vec<u8> {
+    let mut profile = moxcms::ColorProfile::new_display_p3();
+    profile.red_colorant = Default::default();
+    profile.green_colorant = Default::default();
+    profile.blue_colorant = Default::default();
+
+    profile.encode().unwrap()
+}
+
+#[test]
+fn it_keeps_the_colors_of_images_with_unusable_profiles() {
+    let mut png = Cursor::new(Vec::new());
+    let mut encoder = PngEncoder::new(&mut png);
+    encoder.set_icc_profile(unusable_profile()).unwrap();
+    RgbImage::from_pixel(32, 16, image::Rgb(P3_COLOR))
+        .write_with_encoder(encoder)
+        .unwrap();
+
+    let image = create_image(
+        png.get_ref(),
+        FileFormat::PortableNetworkGraphics,
+        &ThumbnailOptions::default(),
+    );
+    assert_color(&image, 8, 8, P3_COLOR, 0);
+}
+
+#[cfg(feature = "openexr")]
+#[test]
+fn it_keeps_the_colors_of_float_images_for_unusable_profiles() {
+    let options = ThumbnailOptions {
+        color_profile: ColorProfile::Icc(unusable_profile()),
+        ..Default::default()
+    };
+    let float_image = |options: &ThumbnailOptions| {
+        create_image(
+            include_bytes!("assets/test.exr"),
+            FileFormat::Openexr,
+            options,
+        )
+    };
+
+    let image = float_image(&options);
+    let expected = float_image(&ThumbnailOptions::default());
+
+    // the transfer curve of the profile is used instead of the srgb curve
+    for (x, y, pixel) in expected.enumerate_pixels() {
+        assert_color(&image, x, y, pixel.0, 1);
+    }
+}
+
+#[test]
+fn it_converts_cmyk_jpegs_with_their_profile() {
+    for bytes in [CMYK_JPG_BYTES, YCCK_JPG_BYTES] {
+        let image = create_image(
+            bytes,
+            FileFormat::JointPhotographicExpertsGroup,
+            &ThumbnailOptions::default(),
+        );
+        assert_color(&image, 4, 8, [255, 255, 255], 4);
+        assert_color(&image, 28, 8, [128, 128, 128], 6);
+    }
+}
+
+#[test]
+fn it_embeds_the_target_profile() {
+    let options = ThumbnailOptions {
+        color_profile: ColorProfile::DisplayP3,
+        embed_color_profile: true,
+        ..Default::default()
+    };
+    let thumbnail = create_thumbnail(P3_PNG_BYTES, FileFormat::PortableNetworkGraphics, &options);
+
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.clone().write_png(&mut buf).unwrap();
+    let mut decoder = PngDecoder::new(Cursor::new(buf.into_inner())).unwrap();
+    assert!(decoder.icc_profile().unwrap().is_some());
+
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.clone().write_jpeg(&mut buf, 90).unwrap();
+    let mut decoder = image::codecs::jpeg::JpegDecoder::new(Cursor::new(buf.into_inner())).unwrap();
+    assert!(decoder.icc_profile().unwrap().is_some());
+
+    #[cfg(feature = "webp")]
+    {
+        let mut buf = Cursor::new(Vec::new());
+        thumbnail
+            .write_webp(&mut buf, &thumbnailer::WebpOptions::default())
+            .unwrap();
+        let mut decoder =
+            image::codecs::webp::WebPDecoder::new(Cursor::new(buf.into_inner())).unwrap();
+        assert!(decoder.icc_profile().unwrap().is_some());
+    }
+}
+
+#[test]
+fn it_doesnt_embed_profiles_by_default() {
+    let thumbnail = create_thumbnail(
+        P3_PNG_BYTES,
+        FileFormat::PortableNetworkGraphics,
+        &ThumbnailOptions::default(),
+    );
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+    let mut decoder = PngDecoder::new(Cursor::new(buf.into_inner())).unwrap();
+
+    assert!(decoder.icc_profile().unwrap().is_none());
+}
+
+#[test]
+fn it_rejects_invalid_target_profiles() {
+    let options = ThumbnailOptions {
+        color_profile: ColorProfile::Icc(vec![0; 16]),
+        ..Default::default()
+    };
+    let result = create_thumbnails_with_options(
+        Cursor::new(P3_PNG_BYTES),
+        FileFormat::PortableNetworkGraphics,
+        [ThumbnailSize::Icon],
+        &options,
+    );
+
+    assert!(matches!(result, Err(ThumbError::InvalidColorProfile)));
+}
+
+fn assert_color(image: &RgbImage, x: u32, y: u32, expected: [u8; 3], tolerance: u8) {
+    let color = image.get_pixel(x, y).0;
+    for (channel, expected) in color.into_iter().zip(expected) {
+        assert!(
+            channel.abs_diff(expected) <= tolerance,
+            "expected {expected:?} but got {color:?}"
+        );
+    }
+}
+
+fn create_image(bytes: &[u8], format: FileFormat, options: &ThumbnailOptions) -> RgbImage {
+    let mut buf = Cursor::new(Vec::new());
+    create_thumbnail(bytes, format, options)
+        .write_png(&mut buf)
+        .unwrap();
+
+    image::load_from_memory(buf.get_ref()).unwrap().into_rgb8()
+}
+
+fn create_thumbnail(bytes: &[u8], format: FileFormat, options: &ThumbnailOptions) -> Thumbnail {
+    create_thumbnails_with_options(
+        Cursor::new(bytes),
+        format,
+        [ThumbnailSize::Custom((32, 16))],
+        options,
+    )
+    .unwrap()
+    .pop()
+    .unwrap()
+}
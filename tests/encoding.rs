@@ -0,0 +1,103 @@
+use file_format::FileFormat;
+use image::ImageFormat;
+use std::io::Cursor;
+use thumbnailer::{
+    create_thumbnails, JpegOptions, OutputFormat, PngOptions, Thumbnail, ThumbnailSize,
+};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+#[test]
+fn it_encodes_the_same_thumbnail_multiple_times() {
+    let thumbnail = create_thumbnail();
+    let formats = [
+        (OutputFormat::Png(PngOptions::default()), ImageFormat::Png),
+        (
+            OutputFormat::Png(PngOptions {
+                high_bit_depth: true,
+                ..Default::default()
+            }),
+            ImageFormat::Png,
+        ),
+        (
+            OutputFormat::Jpeg(JpegOptions::default()),
+            ImageFormat::Jpeg,
+        ),
+        (OutputFormat::Gif, ImageFormat::Gif),
+        #[cfg(feature = "webp")]
+        (
+            OutputFormat::Webp(thumbnailer::WebpOptions::default()),
+            ImageFormat::WebP,
+        ),
+        #[cfg(feature = "qoi")]
+        (OutputFormat::Qoi, ImageFormat::Qoi),
+        #[cfg(feature = "bmp")]
+        (OutputFormat::Bmp, ImageFormat::Bmp),
+    ];
+
+    for (format, image_format) in formats {
+        let mut buf = Vec::new();
+        thumbnail.encode(&format, &mut buf).unwrap();
+
+        assert_eq!(
+            image::guess_format(&buf).unwrap(),
+            image_format,
+            "{format:?} wrote the wrong format"
+        );
+        let image = image::load_from_memory_with_format(&buf, image_format).unwrap();
+        assert_eq!((image.width(), image.height()), thumbnail.size());
+    }
+}
+
+#[cfg(feature = "avif")]
+#[test]
+fn it_encodes_avif() {
+    let thumbnail = create_thumbnail();
+    let mut buf = Vec::new();
+    thumbnail
+        .encode(
+            &OutputFormat::Avif(thumbnailer::AvifOptions::default()),
+            &mut buf,
+        )
+        .unwrap();
+
+    assert_eq!(&buf[4..12], b"ftypavif");
+}
+
+#[test]
+fn it_writes_to_writers_without_seek() {
+    let mut buf = Vec::new();
+    create_thumbnail().write_png(&mut buf).unwrap();
+
+    assert_eq!(image::guess_format(&buf).unwrap(), ImageFormat::Png);
+}
+
+#[test]
+fn it_encodes_like_the_write_methods() {
+    let thumbnail = create_thumbnail();
+    let mut encoded = Vec::new();
+    thumbnail
+        .encode(
+            &OutputFormat::Jpeg(JpegOptions {
+                quality: 60,
+                ..Default::default()
+            }),
+            &mut encoded,
+        )
+        .unwrap();
+    let mut written = Vec::new();
+    thumbnail.write_jpeg(&mut written, 60).unwrap();
+
+    assert_eq!(encoded, written);
+}
+
+fn create_thumbnail() -> Thumbnail {
+    create_thumbnails(
+        Cursor::new(PNG_BYTES),
+        FileFormat::PortableNetworkGraphics,
+        [ThumbnailSize::Small],
+    )
+    .unwrap()
+    .pop()
+    .unwrap()
+}
@@ -0,0 +1,293 @@
+use file_format::FileFormat;
+use std::io::Cursor;
+#[cfg(any(not(feature = "tiff"), feature = "tga"))]
+use thumbnailer::error::ThumbError;
+use thumbnailer::error::ThumbResult;
+use thumbnailer::{create_thumbnails, Thumbnail, ThumbnailSize};
+#[cfg(any(
+    feature = "tiff",
+    feature = "bmp",
+    feature = "ico",
+    feature = "tga",
+    feature = "pnm",
+    feature = "qoi",
+    feature = "dds",
+    feature = "hdr",
+    feature = "openexr",
+    feature = "jxl"
+))]
+use thumbnailer::{create_thumbnails_unknown_type, ToneMapping};
+
+const TIFF_BYTES: &[u8] = include_bytes!("assets/test.tiff");
+#[cfg(feature = "bmp")]
+const BMP_BYTES: &[u8] = include_bytes!("assets/test.bmp");
+#[cfg(feature = "ico")]
+const ICO_BYTES: &[u8] = include_bytes!("assets/test.ico");
+#[cfg(feature = "tga")]
+const TGA_BYTES: &[u8] = include_bytes!("assets/test.tga");
+#[cfg(feature = "pnm")]
+const PPM_BYTES: &[u8] = include_bytes!("assets/test.ppm");
+#[cfg(feature = "qoi")]
+const QOI_BYTES: &[u8] = include_bytes!("assets/test.qoi");
+#[cfg(feature = "dds")]
+const DDS_BYTES: &[u8] = include_bytes!("assets/test.dds");
+#[cfg(feature = "hdr")]
+const HDR_BYTES: &[u8] = include_bytes!("assets/test.hdr");
+#[cfg(feature = "openexr")]
+const EXR_BYTES: &[u8] = include_bytes!("assets/test.exr");
+/// The srgb values of the center of the other fixtures are stored as linear light
+/// in the hdr fixtures, so they are brighter once they are encoded as srgb
+#[cfg(any(feature = "hdr", feature = "openexr"))]
+const HDR_CENTER: [u8; 3] = [79, 202, 64];
+#[cfg(feature = "jxl")]
+const JXL_BYTES: &[u8] = include_bytes!("assets/test.jxl");
+/// `test.jpg` losslessly recompressed by libjxl like `cjxl --lossless_jpeg=1` does
+#[cfg(feature = "jxl")]
+const JBRD_JXL_BYTES: &[u8] = include_bytes!("assets/test_jbrd.jxl");
+#[cfg(feature = "jxl")]
+const JPG_BYTES: &[u8] = include_bytes!("assets/test.jpg");
+
+/// Keeps the size of the 64x48 fixtures
+const FIXTURE_SIZE: ThumbnailSize = ThumbnailSize::Custom((64, 48));
+
+#[cfg(feature = "tiff")]
+#[test]
+fn it_creates_thumbnails_for_tiff() {
+    assert_decoded(
+        create_thumbnail(TIFF_BYTES, FileFormat::TagImageFileFormat),
+        [20, 151, 13],
+    );
+    assert_decoded(create_thumbnail_unknown(TIFF_BYTES), [20, 151, 13]);
+}
+
+#[cfg(feature = "bmp")]
+#[test]
+fn it_creates_thumbnails_for_bmp() {
+    assert_decoded(
+        create_thumbnail(BMP_BYTES, FileFormat::WindowsBitmap),
+        [20, 151, 13],
+    );
+    assert_decoded(create_thumbnail_unknown(BMP_BYTES), [20, 151, 13]);
+}
+
+#[cfg(feature = "ico")]
+#[test]
+fn it_creates_thumbnails_for_ico() {
+    assert_decoded(
+        create_thumbnail(ICO_BYTES, FileFormat::WindowsIcon),
+        [20, 151, 13],
+    );
+    assert_decoded(create_thumbnail_unknown(ICO_BYTES), [20, 151, 13]);
+}
+
+#[cfg(feature = "tga")]
+#[test]
+fn it_creates_thumbnails_for_tga() {
+    assert_decoded(
+        create_thumbnail(TGA_BYTES, FileFormat::ArbitraryBinaryData),
+        [20, 151, 13],
+    );
+    assert_decoded(create_thumbnail_unknown(TGA_BYTES), [20, 151, 13]);
+}
+
+#[cfg(feature = "tga")]
+#[test]
+fn it_reports_files_without_a_tga_header_as_unsupported() {
+    let result = create_thumbnail(
+        b"neither a tga image nor anything else",
+        FileFormat::ArbitraryBinaryData,
+    );
+
+    assert!(matches!(result, Err(ThumbError::Unsupported(_))));
+}
+
+#[cfg(feature = "tga")]
+#[test]
+fn it_reports_random_bytes_as_unsupported() {
+    // the xorshift generator creates the same bytes on every run
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next_byte = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as u8
+    };
+
+    for _ in 0..1000 {
+        let bytes: Vec<u8> = (0..1024).map(|_| next_byte()).collect();
+        let result = create_thumbnail(&bytes, FileFormat::ArbitraryBinaryData);
+
+        assert!(
+            matches!(result, Err(ThumbError::Unsupported(_))),
+            "{result:?}"
+        );
+    }
+}
+
+#[cfg(feature = "tga")]
+#[test]
+fn it_reports_errors_of_truncated_tga_images() {
+    let result = create_thumbnail(
+        &TGA_BYTES[..TGA_BYTES.len() / 2],
+        FileFormat::ArbitraryBinaryData,
+    );
+
+    assert!(matches!(result, Err(ThumbError::Image(_))), "{result:?}");
+}
+
+#[cfg(feature = "pnm")]
+#[test]
+fn it_creates_thumbnails_for_pnm() {
+    assert_decoded(
+        create_thumbnail(PPM_BYTES, FileFormat::PortablePixmap),
+        [20, 151, 13],
+    );
+    assert_decoded(create_thumbnail_unknown(PPM_BYTES), [20, 151, 13]);
+}
+
+#[cfg(feature = "qoi")]
+#[test]
+fn it_creates_thumbnails_for_qoi() {
+    assert_decoded(
+        create_thumbnail(QOI_BYTES, FileFormat::QuiteOkImage),
+        [20, 151, 13],
+    );
+    assert_decoded(create_thumbnail_unknown(QOI_BYTES), [20, 151, 13]);
+}
+
+#[cfg(feature = "dds")]
+#[test]
+fn it_creates_thumbnails_for_dds() {
+    assert_decoded(
+        create_thumbnail(DDS_BYTES, FileFormat::MicrosoftDirectdrawSurface),
+        [128, 130, 126],
+    );
+    assert_decoded(create_thumbnail_unknown(DDS_BYTES), [128, 130, 126]);
+}
+
+#[cfg(feature = "hdr")]
+#[test]
+fn it_creates_thumbnails_for_hdr() {
+    assert_decoded(
+        create_thumbnail(HDR_BYTES, FileFormat::RadianceHdr),
+        HDR_CENTER,
+    );
+    assert_decoded(create_thumbnail_unknown(HDR_BYTES), HDR_CENTER);
+}
+
+#[cfg(feature = "openexr")]
+#[test]
+fn it_creates_thumbnails_for_openexr() {
+    assert_decoded(create_thumbnail(EXR_BYTES, FileFormat::Openexr), HDR_CENTER);
+    assert_decoded(create_thumbnail_unknown(EXR_BYTES), HDR_CENTER);
+}
+
+#[cfg(feature = "jxl")]
+#[test]
+fn it_creates_thumbnails_for_jxl() {
+    assert_decoded(
+        create_thumbnail(JXL_BYTES, FileFormat::JpegXl),
+        [20, 151, 13],
+    );
+    assert_decoded(create_thumbnail_unknown(JXL_BYTES), [20, 151, 13]);
+}
+
+#[cfg(feature = "jxl")]
+#[test]
+fn it_creates_thumbnails_for_recompressed_jpegs_in_jxl() {
+    let mut reconstructed = Vec::new();
+    jxl_oxide::JxlImage::builder()
+        .read(Cursor::new(JBRD_JXL_BYTES))
+        .unwrap()
+        .reconstruct_jpeg(&mut reconstructed)
+        .unwrap();
+    assert_eq!(reconstructed, JPG_BYTES);
+
+    let size = ThumbnailSize::Medium;
+    let mut jxl =
+        create_thumbnails(Cursor::new(JBRD_JXL_BYTES), FileFormat::JpegXl, [size]).unwrap();
+    let mut jpg = create_thumbnails(
+        Cursor::new(JPG_BYTES),
+        FileFormat::JointPhotographicExpertsGroup,
+        [size],
+    )
+    .unwrap();
+    let (jxl, jpg) = (encode_rgb8(jxl.remove(0)), encode_rgb8(jpg.remove(0)));
+
+    assert_eq!(jxl.dimensions(), jpg.dimensions());
+    let max_difference = jxl
+        .as_raw()
+        .iter()
+        .zip(jpg.as_raw())
+        .map(|(a, b)| a.abs_diff(*b))
+        .max()
+        .unwrap();
+    assert!(max_difference <= 4, "{max_difference}");
+}
+
+#[cfg(not(feature = "tiff"))]
+#[test]
+fn it_reports_tiff_as_unsupported_without_the_feature() {
+    let result = create_thumbnail(TIFF_BYTES, FileFormat::TagImageFileFormat);
+
+    assert!(matches!(result, Err(ThumbError::Unsupported(_))));
+}
+
+/// Asserts the size of the fixture and the color of its center pixel.
+/// The thumbnails keep the size of the fixtures, so their pixels aren't resampled,
+/// and hdr fixtures are clamped to keep their values.
+#[cfg(any(
+    feature = "tiff",
+    feature = "bmp",
+    feature = "ico",
+    feature = "tga",
+    feature = "pnm",
+    feature = "qoi",
+    feature = "dds",
+    feature = "hdr",
+    feature = "openexr",
+    feature = "jxl"
+))]
+fn assert_decoded(result: ThumbResult<Vec<Thumbnail>>, center: [u8; 3]) {
+    let mut thumbnail = result.unwrap().pop().unwrap();
+    thumbnail.set_tone_mapping(ToneMapping::Clamp);
+    let mut buf = Cursor::new(Vec::new());
+    assert_eq!(thumbnail.size(), (64, 48));
+    thumbnail.write_png(&mut buf).unwrap();
+    let image = image::load_from_memory(buf.get_ref()).unwrap().into_rgb8();
+    let pixel = image.get_pixel(32, 24).0;
+
+    assert!(
+        pixel.iter().zip(center).all(|(a, b)| a.abs_diff(b) <= 2),
+        "{pixel:?} != {center:?}"
+    );
+}
+
+#[cfg(feature = "jxl")]
+fn encode_rgb8(thumbnail: Thumbnail) -> image::RgbImage {
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+    image::load_from_memory(buf.get_ref()).unwrap().into_rgb8()
+}
+
+fn create_thumbnail(bytes: &[u8], format: FileFormat) -> ThumbResult<Vec<Thumbnail>> {
+    let reader = Cursor::new(bytes);
+    create_thumbnails(reader, format, [FIXTURE_SIZE])
+}
+
+#[cfg(any(
+    feature = "tiff",
+    feature = "bmp",
+    feature = "ico",
+    feature = "tga",
+    feature = "pnm",
+    feature = "qoi",
+    feature = "dds",
+    feature = "hdr",
+    feature = "openexr",
+    feature = "jxl"
+))]
+fn create_thumbnail_unknown(bytes: &[u8]) -> ThumbResult<Vec<Thumbnail>> {
+    let reader = Cursor::new(bytes);
+    create_thumbnails_unknown_type(reader, [FIXTURE_SIZE])
+}
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+use thumbnailer::freedesktop::{file_uri, CacheSize, ThumbnailCache};
+
+const JPG_BYTES: &[u8] = include_bytes!("assets/test.jpg");
+
+#[test]
+fn it_names_entries_by_the_md5_of_the_uri() {
+    let cache = ThumbnailCache::new("/cache/thumbnails");
+
+    // the example of the thumbnail managing standard
+    assert_eq!(
+        cache.thumbnail_path("file:///home/jens/photos/me.png", CacheSize::Normal),
+        Path::new("/cache/thumbnails/normal/c6ee772d9e49320e97ec29a7eb5b1697.png")
+    );
+    assert_eq!(
+        cache.failure_path("file:///home/jens/photos/me.png", "app-1.0"),
+        Path::new("/cache/thumbnails/fail/app-1.0/c6ee772d9e49320e97ec29a7eb5b1697.png")
+    );
+}
+
+#[test]
+fn it_escapes_file_uris() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("my photo #1.jpg");
+    fs::write(&path, JPG_BYTES).unwrap();
+    let uri = file_uri(&path).unwrap();
+
+    assert!(uri.starts_with("file:///"));
+    assert!(uri.ends_with("/my%20photo%20%231.jpg"), "{uri}");
+}
+
+#[test]
+fn it_creates_thumbnails_with_the_required_metadata() {
+    let (dir, file) = source_file();
+    let cache = ThumbnailCache::new(dir.path().join("thumbnails"));
+    let path = cache.create(&file, CacheSize::Normal).unwrap();
+    let (text, (width, height), format) = read_png(&path);
+    let mtime = fs::metadata(&file)
+        .unwrap()
+        .modified()
+        .unwrap()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    assert_eq!(
+        path,
+        cache.thumbnail_path(&file_uri(&file).unwrap(), CacheSize::Normal)
+    );
+    assert_eq!(text["Thumb::URI"], file_uri(&file).unwrap());
+    assert_eq!(text["Thumb::MTime"], mtime.to_string());
+    assert_eq!(text["Thumb::Size"], JPG_BYTES.len().to_string());
+    assert_eq!(text["Thumb::Mime"], "image/jpeg");
+    assert_eq!(width.max(height), 128);
+    assert_eq!(format, (png::ColorType::Rgba, png::BitDepth::Eight));
+    assert_eq!(cache.lookup(&file, CacheSize::Normal).unwrap(), Some(path));
+    assert_eq!(cache.lookup(&file, CacheSize::Large).unwrap(), None);
+}
+
+#[test]
+fn it_keeps_the_aspect_ratio() {
+    let (dir, file) = source_file();
+    let cache = ThumbnailCache::new(dir.path().join("thumbnails"));
+    let source = image::load_from_memory(JPG_BYTES).unwrap();
+    let path = cache.create(&file, CacheSize::Large).unwrap();
+    let (_, (width, height), _) = read_png(&path);
+
+    let ratio = width as f32 / height as f32;
+    let source_ratio = source.width() as f32 / source.height() as f32;
+    assert!(
+        (ratio - source_ratio).abs() < 0.05,
+        "{ratio} != {source_ratio}"
+    );
+}
+
+#[test]
+fn it_ignores_outdated_thumbnails() {
+    let (dir, file) = source_file();
+    let cache = ThumbnailCache::new(dir.path().join("thumbnails"));
+    cache.create(&file, CacheSize::Normal).unwrap();
+    set_modified(&file, SystemTime::now() + Duration::from_secs(60));
+
+    assert_eq!(cache.lookup(&file, CacheSize::Normal).unwrap(), None);
+    let path = cache.create(&file, CacheSize::Normal).unwrap();
+    assert_eq!(cache.lookup(&file, CacheSize::Normal).unwrap(), Some(path));
+}
+
+#[test]
+fn it_records_failures() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("broken.jpg");
+    fs::write(&file, &JPG_BYTES[..100]).unwrap();
+    let cache = ThumbnailCache::new(dir.path().join("thumbnails"));
+
+    assert!(cache.create(&file, CacheSize::Normal).is_err());
+    assert!(!cache.has_failed(&file, "app-1.0").unwrap());
+    let path = cache.record_failure(&file, "app-1.0").unwrap();
+    let (text, _, format) = read_png(&path);
+
+    assert!(path.starts_with(dir.path().join("thumbnails/fail/app-1.0")));
+    assert_eq!(text["Thumb::URI"], file_uri(&file).unwrap());
+    assert_eq!(format, (png::ColorType::Rgba, png::BitDepth::Eight));
+    assert!(cache.has_failed(&file, "app-1.0").unwrap());
+    assert!(!cache.has_failed(&file, "other-app-1.0").unwrap());
+
+    set_modified(&file, SystemTime::now() + Duration::from_secs(60));
+    assert!(!cache.has_failed(&file, "app-1.0").unwrap());
+}
+
+#[cfg(feature = "hdr")]
+#[test]
+fn it_tone_maps_with_the_operator_of_the_options() {
+    use thumbnailer::{ThumbnailOptions, ToneMapping};
+
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("bright.hdr");
+    fs::write(&file, include_bytes!("assets/test_bright.hdr")).unwrap();
+    let brightest = |tone_mapping| {
+        let cache = ThumbnailCache::new(dir.path().join(format!("{tone_mapping:?}")));
+        let options = ThumbnailOptions {
+            tone_mapping,
+            ..Default::default()
+        };
+        let path = cache
+            .create_with_options(&file, CacheSize::Normal, &options)
+            .unwrap();
+        let image = image::open(path).unwrap().into_rgb8();
+        image.pixels().map(|pixel| pixel[0]).max().unwrap()
+    };
+
+    assert_eq!(brightest(ToneMapping::Clamp), 255);
+    assert!(brightest(ToneMapping::Reinhard) < 255);
+}
+
+#[cfg(unix)]
+#[test]
+fn it_only_allows_the_owner_to_access_the_cache() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (dir, file) = source_file();
+    let cache = ThumbnailCache::new(dir.path().join("thumbnails"));
+    let path = cache.create(&file, CacheSize::Normal).unwrap();
+    let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+    assert_eq!(mode(&path), 0o600);
+    assert_eq!(mode(path.parent().unwrap()), 0o700);
+}
+
+fn source_file() -> (TempDir, PathBuf) {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("test.jpg");
+    fs::write(&file, JPG_BYTES).unwrap();
+
+    (dir, file)
+}
+
+fn set_modified(path: &Path, time: SystemTime) {
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(time)
+        .unwrap();
+}
+
+/// Returns the text chunks, dimensions and the color type with the bit depth of the png
+fn read_png(
+    path: &Path,
+) -> (
+    HashMap<String, String>,
+    (u32, u32),
+    (png::ColorType, png::BitDepth),
+) {
+    let decoder = png::Decoder::new(BufReader::new(File::open(path).unwrap()));
+    let reader = decoder.read_info().unwrap();
+    let info = reader.info();
+    let text = info
+        .uncompressed_latin1_text
+        .iter()
+        .map(|chunk| (chunk.keyword.clone(), chunk.text.clone()))
+        .collect();
+
+    (
+        text,
+        (info.width, info.height),
+        (info.color_type, info.bit_depth),
+    )
+}
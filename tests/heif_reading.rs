@@ -0,0 +1,161 @@
+use file_format::FileFormat;
+use image::{DynamicImage, GenericImage, RgbaImage};
+use std::io::Cursor;
+#[cfg(feature = "avif")]
+use thumbnailer::create_thumbnails_unknown_type;
+use thumbnailer::error::ThumbError;
+use thumbnailer::error::ThumbResult;
+use thumbnailer::{create_thumbnails, Thumbnail, ThumbnailSize};
+
+const AVIF_BYTES: &[u8] = include_bytes!("assets/test.avif");
+/// The fixture is rotated by 90 degrees anti-clockwise and mirrored about the horizontal axis
+const ROTATED_AVIF_BYTES: &[u8] = include_bytes!("assets/test_rotated.avif");
+/// The fixture is a 2x1 grid of the color item of `test.avif`
+const GRID_AVIF_BYTES: &[u8] = include_bytes!("assets/test_grid.avif");
+/// The hevc tile of the heic fixtures is taken from `tests/exif.heic` of kamadak-exif
+/// (BSD-2-Clause, copyright KAMADA Ken'ichi)
+const HEIC_BYTES: &[u8] = include_bytes!("assets/test.heic");
+/// The fixture is a 2x1 grid of the tile of `test.heic` that is rotated by 90 degrees
+/// anti-clockwise and mirrored about the horizontal axis
+const GRID_HEIC_BYTES: &[u8] = include_bytes!("assets/test_grid.heic");
+
+#[cfg(feature = "avif")]
+#[test]
+fn it_creates_thumbnails_for_avif() {
+    let thumbnail = create_avif_thumbnail().unwrap().pop().unwrap();
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+    let image = image::load_from_memory(buf.get_ref()).unwrap().into_rgba8();
+
+    assert_eq!(image.dimensions(), (128, 128));
+    assert_eq!(image.get_pixel(10, 64)[3], 255);
+    assert_eq!(image.get_pixel(118, 64)[3], 0);
+}
+
+#[cfg(feature = "avif")]
+#[test]
+fn it_rotates_and_mirrors_avif() {
+    let thumbnail = create_thumbnail(ROTATED_AVIF_BYTES).unwrap();
+    assert_rotated_and_mirrored(thumbnail);
+}
+
+#[cfg(feature = "avif")]
+#[test]
+fn it_creates_thumbnails_for_avif_unknown() {
+    let reader = Cursor::new(AVIF_BYTES);
+    create_thumbnails_unknown_type(reader, [ThumbnailSize::Small]).unwrap();
+}
+
+#[cfg(not(feature = "avif"))]
+#[test]
+fn it_creates_thumbnails_for_avif_with_ffmpeg() {
+    match create_avif_thumbnail() {
+        Ok(_) => {}
+        Err(ThumbError::Unsupported(_)) => {
+            // ffmpeg is not installed
+        }
+        Err(e) => panic!("failed to create thumbnails {e}"),
+    }
+}
+
+#[cfg(not(feature = "avif"))]
+#[test]
+fn it_rotates_and_mirrors_avif_with_ffmpeg() {
+    match create_thumbnail(ROTATED_AVIF_BYTES) {
+        Ok(thumbnail) => assert_rotated_and_mirrored(thumbnail),
+        Err(ThumbError::Unsupported(_)) => {
+            // ffmpeg is not installed
+        }
+        Err(e) => panic!("failed to create thumbnails {e}"),
+    }
+}
+
+/// Grid images aren't supported by the avif parser, so they are stitched together by ffmpeg
+#[test]
+fn it_creates_thumbnails_for_grid_avif() {
+    let result = create_image(GRID_AVIF_BYTES, FileFormat::Av1ImageFileFormat, 128);
+
+    match result {
+        Ok(image) => assert_eq!(image.dimensions(), (128, 48)),
+        Err(ThumbError::Unsupported(_)) => {
+            // ffmpeg is not installed
+        }
+        Err(e) => panic!("failed to create thumbnails {e}"),
+    }
+}
+
+/// The tiles of grid images are stitched together by ffmpeg 7.1 and newer,
+/// before the rotation and mirroring of the grid is applied
+#[test]
+fn it_rotates_and_mirrors_grid_heic() {
+    let format = FileFormat::HighEfficiencyImageCoding;
+    let (tile, grid) = match (
+        create_image(HEIC_BYTES, format, 64),
+        create_image(GRID_HEIC_BYTES, format, 128),
+    ) {
+        (Ok(tile), Ok(grid)) => (tile, grid),
+        (Err(ThumbError::Unsupported(_)), _) | (_, Err(ThumbError::Unsupported(_))) => {
+            // ffmpeg is not installed
+            return;
+        }
+        (Err(e), _) | (_, Err(e)) => panic!("failed to create thumbnails {e}"),
+    };
+    let mut row = RgbaImage::new(128, 64);
+    row.copy_from(&tile, 0, 0).unwrap();
+    row.copy_from(&tile, 64, 0).unwrap();
+    let expected = DynamicImage::ImageRgba8(row)
+        .rotate270()
+        .flipv()
+        .into_rgba8();
+
+    assert_eq!(grid.dimensions(), expected.dimensions());
+    assert!(grid.pixels().zip(expected.pixels()).all(|(a, b)| a
+        .0
+        .iter()
+        .zip(b.0)
+        .all(|(a, b)| a.abs_diff(b) <= 2)));
+}
+
+/// The left half of the fixture is opaque, so it ends up at the top
+fn assert_rotated_and_mirrored(thumbnail: Thumbnail) {
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+    let image = image::load_from_memory(buf.get_ref()).unwrap().into_rgba8();
+
+    assert_eq!(image.get_pixel(64, 10)[3], 255);
+    assert_eq!(image.get_pixel(64, 118)[3], 0);
+}
+
+fn create_thumbnail(bytes: &[u8]) -> ThumbResult<Thumbnail> {
+    let reader = Cursor::new(bytes);
+    let mut thumbnails = create_thumbnails(
+        reader,
+        FileFormat::Av1ImageFileFormat,
+        [ThumbnailSize::Small],
+    )?;
+
+    Ok(thumbnails.pop().unwrap())
+}
+
+fn create_avif_thumbnail() -> ThumbResult<Vec<Thumbnail>> {
+    let reader = Cursor::new(AVIF_BYTES);
+    create_thumbnails(
+        reader,
+        FileFormat::Av1ImageFileFormat,
+        [ThumbnailSize::Small],
+    )
+}
+
+fn create_image(bytes: &[u8], format: FileFormat, max_edge: u32) -> ThumbResult<RgbaImage> {
+    let thumbnail = create_thumbnails(
+        Cursor::new(bytes),
+        format,
+        [ThumbnailSize::MaxEdge(max_edge)],
+    )?
+    .pop()
+    .unwrap();
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+
+    Ok(image::load_from_memory(buf.get_ref()).unwrap().into_rgba8())
+}
@@ -5,7 +5,6 @@ const WEBP_BYTES: &[u8] = include_bytes!("assets/test.webp");
 use crate::ImageType::{Jpeg, Png, Webp};
 use file_format::FileFormat;
 use std::io::Cursor;
-use std::str::FromStr;
 use thumbnailer::error::ThumbResult;
 use thumbnailer::{create_thumbnails, create_thumbnails_unknown_type, Thumbnail, ThumbnailSize};
 
@@ -126,15 +125,15 @@ fn create_thumbnails_unknown(
 ) -> ThumbResult<Vec<Thumbnail>> {
     match image_type {
         ImageType::Png => {
-            let mut reader = Cursor::new(PNG_BYTES);
+            let reader = Cursor::new(PNG_BYTES);
             create_thumbnails_unknown_type(reader, [size])
         }
         ImageType::Jpeg => {
-            let mut reader = Cursor::new(JPG_BYTES);
+            let reader = Cursor::new(JPG_BYTES);
             create_thumbnails_unknown_type(reader, [size])
         }
         ImageType::Webp => {
-            let mut reader = Cursor::new(WEBP_BYTES);
+            let reader = Cursor::new(WEBP_BYTES);
             create_thumbnails_unknown_type(reader, [size])
         }
     }
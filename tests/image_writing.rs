@@ -1,6 +1,5 @@
 use file_format::FileFormat;
 use std::io::Cursor;
-use std::str::FromStr;
 use thumbnailer::error::ThumbResult;
 use thumbnailer::{create_thumbnails, create_thumbnails_samplefilter, FilterType, ThumbnailSize};
 
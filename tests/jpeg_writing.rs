@@ -0,0 +1,181 @@
+use file_format::FileFormat;
+use std::io::Cursor;
+use thumbnailer::{
+    create_thumbnails, create_thumbnails_with_options, ChromaSubsampling, Copyright, JpegOptions,
+    OutputFormat, Thumbnail, ThumbnailOptions, ThumbnailSize,
+};
+
+const JPG_BYTES: &[u8] = include_bytes!("assets/test.jpg");
+// contains exif copyright and artist fields
+const COPYRIGHT_JPG_BYTES: &[u8] = include_bytes!("assets/test_copyright.jpg");
+
+const BASELINE_MARKER: u8 = 0xC0;
+const PROGRESSIVE_MARKER: u8 = 0xC2;
+
+#[test]
+fn it_writes_progressive_jpegs() {
+    let thumbnail = create_thumbnail(JPG_BYTES);
+    let baseline = encode(&thumbnail, &JpegOptions::default());
+    let progressive = encode(
+        &thumbnail,
+        &JpegOptions {
+            progressive: true,
+            ..Default::default()
+        },
+    );
+
+    assert_eq!(frame_header(&baseline).0, BASELINE_MARKER);
+    assert_eq!(frame_header(&progressive).0, PROGRESSIVE_MARKER);
+    assert!(image::load_from_memory(&progressive).is_ok());
+}
+
+#[test]
+fn it_subsamples_the_chroma_channels() {
+    let thumbnail = create_thumbnail(JPG_BYTES);
+
+    for (subsampling, luma_sampling) in [
+        (ChromaSubsampling::Yuv444, 0x11),
+        (ChromaSubsampling::Yuv422, 0x21),
+        (ChromaSubsampling::Yuv420, 0x22),
+    ] {
+        let options = JpegOptions {
+            subsampling,
+            ..Default::default()
+        };
+        let jpeg = encode(&thumbnail, &options);
+
+        assert_eq!(frame_header(&jpeg).1, luma_sampling, "{subsampling:?}");
+        assert!(image::load_from_memory(&jpeg).is_ok());
+    }
+}
+
+#[test]
+fn it_optimizes_huffman_tables() {
+    let thumbnail = create_thumbnail(JPG_BYTES);
+    let standard = encode(&thumbnail, &JpegOptions::default());
+    let optimized = encode(
+        &thumbnail,
+        &JpegOptions {
+            optimize_huffman_tables: true,
+            ..Default::default()
+        },
+    );
+
+    assert!(optimized.len() < standard.len());
+    assert_eq!(
+        image::load_from_memory(&optimized).unwrap(),
+        image::load_from_memory(&standard).unwrap()
+    );
+}
+
+#[test]
+fn it_reads_the_copyright_of_the_source() {
+    let thumbnail = create_thumbnail(COPYRIGHT_JPG_BYTES);
+
+    assert_eq!(thumbnail.copyright(), &expected_copyright());
+    assert_eq!(
+        create_thumbnail(JPG_BYTES).copyright(),
+        &Copyright::default()
+    );
+}
+
+#[test]
+fn it_only_reads_the_copyright_when_enabled() {
+    let thumbnail = create_thumbnails(
+        Cursor::new(COPYRIGHT_JPG_BYTES),
+        FileFormat::JointPhotographicExpertsGroup,
+        [ThumbnailSize::Icon],
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+
+    assert_eq!(thumbnail.copyright(), &Copyright::default());
+}
+
+#[test]
+fn it_ignores_malformed_exif_data() {
+    // an app1 segment with an exif header followed by garbage instead of tiff data
+    let mut jpeg = COPYRIGHT_JPG_BYTES[..2].to_vec();
+    jpeg.extend_from_slice(&[0xFF, 0xE1, 0x00, 0x10]);
+    jpeg.extend_from_slice(b"Exif\0\0garbage!");
+    jpeg.extend_from_slice(&JPG_BYTES[2..]);
+
+    assert_eq!(create_thumbnail(&jpeg).copyright(), &Copyright::default());
+}
+
+#[test]
+fn it_keeps_the_copyright_when_enabled() {
+    let thumbnail = create_thumbnail(COPYRIGHT_JPG_BYTES);
+    let options = JpegOptions {
+        keep_copyright: true,
+        ..Default::default()
+    };
+    let jpeg = encode(&thumbnail, &options);
+    let exif = exif::Reader::new()
+        .read_from_container(&mut Cursor::new(&jpeg))
+        .unwrap();
+    let field = exif
+        .get_field(exif::Tag::Copyright, exif::In::PRIMARY)
+        .unwrap();
+
+    assert_eq!(
+        field.display_value().to_string(),
+        "\"Copyright 2024 Example Photos\""
+    );
+    // the copyright of the thumbnail is read back from the written exif data
+    let written = create_thumbnail(&jpeg);
+    assert_eq!(written.copyright(), &expected_copyright());
+}
+
+#[test]
+fn it_drops_the_copyright_by_default() {
+    let thumbnail = create_thumbnail(COPYRIGHT_JPG_BYTES);
+    let jpeg = encode(&thumbnail, &JpegOptions::default());
+
+    assert!(exif::Reader::new()
+        .read_from_container(&mut Cursor::new(&jpeg))
+        .is_err());
+}
+
+fn expected_copyright() -> Copyright {
+    Copyright {
+        copyright: Some(String::from("Copyright 2024 Example Photos")),
+        artist: Some(String::from("Jane Doe")),
+    }
+}
+
+/// Returns the start of frame marker and the sampling factors of the luma channel
+fn frame_header(jpeg: &[u8]) -> (u8, u8) {
+    let position = jpeg
+        .windows(2)
+        .position(|bytes| bytes[0] == 0xFF && matches!(bytes[1], 0xC0..=0xC2))
+        .unwrap();
+    // marker, length, precision, height, width, component count and component id
+    (jpeg[position + 1], jpeg[position + 11])
+}
+
+fn encode(thumbnail: &Thumbnail, options: &JpegOptions) -> Vec<u8> {
+    let mut buf = Vec::new();
+    thumbnail
+        .encode(&OutputFormat::Jpeg(options.clone()), &mut buf)
+        .unwrap();
+
+    buf
+}
+
+fn create_thumbnail(bytes: &[u8]) -> Thumbnail {
+    let options = ThumbnailOptions {
+        read_copyright: true,
+        ..Default::default()
+    };
+    create_thumbnails_with_options(
+        Cursor::new(bytes),
+        FileFormat::JointPhotographicExpertsGroup,
+        [ThumbnailSize::Icon],
+        &options,
+    )
+    .unwrap()
+    .pop()
+    .unwrap()
+}
@@ -0,0 +1,80 @@
+use file_format::FileFormat;
+use image::GrayImage;
+use std::io::Cursor;
+use thumbnailer::{create_thumbnails_with_options, FilterType, ThumbnailOptions, ThumbnailSize};
+
+// black and white pixels alternating, which average to 50% linear light
+const CHECKERBOARD_BYTES: &[u8] = include_bytes!("assets/test_checkerboard.png");
+
+// 50% linear light encoded as srgb
+const LINEAR_GREY: u8 = 188;
+// the average of the srgb encoded values, which appears too dark
+const SRGB_GREY: u8 = 128;
+
+const FILTERS: [FilterType; 4] = [
+    FilterType::Triangle,
+    FilterType::CatmullRom,
+    FilterType::Gaussian,
+    FilterType::Lanczos3,
+];
+
+#[test]
+fn it_resizes_checkerboards_in_linear_light() {
+    for filter in FILTERS {
+        let image = create_image(filter.clone(), true);
+        assert_grey(&image, LINEAR_GREY, &filter);
+    }
+}
+
+#[test]
+fn it_resizes_checkerboards_in_srgb_by_default() {
+    for filter in FILTERS {
+        let image = create_image(filter.clone(), false);
+        assert_grey(&image, SRGB_GREY, &filter);
+    }
+}
+
+#[test]
+fn it_keeps_pixel_values_with_nearest_filter() {
+    let linear = create_image(FilterType::Nearest, true);
+    let srgb = create_image(FilterType::Nearest, false);
+
+    assert_eq!(linear, srgb);
+    assert!(linear
+        .pixels()
+        .all(|pixel| pixel[0] == 0 || pixel[0] == 255));
+}
+
+fn assert_grey(image: &GrayImage, expected: u8, filter: &FilterType) {
+    // the edges are affected by the clamped filter kernels
+    for y in 2..14 {
+        for x in 2..14 {
+            let value = image.get_pixel(x, y)[0];
+            assert!(
+                value.abs_diff(expected) <= 3,
+                "{filter:?} produced {value} instead of {expected} at {x},{y}"
+            );
+        }
+    }
+}
+
+fn create_image(filter: FilterType, linear_light: bool) -> GrayImage {
+    let options = ThumbnailOptions {
+        filter,
+        linear_light,
+        ..Default::default()
+    };
+    let thumbnail = create_thumbnails_with_options(
+        Cursor::new(CHECKERBOARD_BYTES),
+        FileFormat::PortableNetworkGraphics,
+        [ThumbnailSize::Custom((16, 16))],
+        &options,
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+
+    image::load_from_memory(buf.get_ref()).unwrap().into_luma8()
+}
@@ -0,0 +1,231 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use file_format::FileFormat;
+use image::DynamicImage;
+use std::io::Cursor;
+use thumbnailer::error::ThumbError;
+use thumbnailer::{
+    create_thumbnails, create_thumbnails_with_options, ColorProfile, FilterType, LqipOptions,
+    OutputFormat, PngOptions, Thumbnail, ThumbnailOptions, ThumbnailSize,
+};
+
+const JPG_BYTES: &[u8] = include_bytes!("assets/test.jpg");
+
+#[test]
+fn it_creates_jpeg_data_uris_by_default() {
+    let thumbnail = create_thumbnail();
+    let uri = thumbnail.to_data_uri(&LqipOptions::default()).unwrap();
+    let image = decode(&uri, "image/jpeg");
+
+    assert!(uri.len() <= 1024);
+    assert_eq!(image.width().max(image.height()), 24);
+}
+
+#[test]
+fn it_keeps_the_aspect_ratio() {
+    let thumbnail = create_thumbnails(
+        Cursor::new(JPG_BYTES),
+        FileFormat::JointPhotographicExpertsGroup,
+        [ThumbnailSize::Custom((200, 100))],
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+    let uri = thumbnail.to_data_uri(&LqipOptions::default()).unwrap();
+    let image = decode(&uri, "image/jpeg");
+
+    assert_eq!((image.width(), image.height()), (24, 12));
+}
+
+#[test]
+fn it_encodes_png_and_webp_data_uris() {
+    let thumbnail = create_thumbnail();
+    let formats = [
+        (OutputFormat::Png(PngOptions::default()), "image/png"),
+        #[cfg(feature = "webp")]
+        (
+            OutputFormat::Webp(thumbnailer::WebpOptions::default()),
+            "image/webp",
+        ),
+    ];
+
+    for (format, mime) in formats {
+        let options = LqipOptions {
+            format,
+            ..Default::default()
+        };
+        let uri = thumbnail.to_data_uri(&options).unwrap();
+
+        assert!(uri.len() <= options.max_bytes);
+        decode(&uri, mime);
+    }
+}
+
+#[test]
+fn it_shrinks_the_placeholder_to_fit_into_the_limit() {
+    let thumbnail = create_thumbnail();
+    let options = LqipOptions {
+        size: 32,
+        format: OutputFormat::Png(PngOptions::default()),
+        max_bytes: 1400,
+        ..Default::default()
+    };
+    let uri = thumbnail.to_data_uri(&options).unwrap();
+    let image = decode(&uri, "image/png");
+
+    assert!(uri.len() <= 1400);
+    assert!(image.width() < 32);
+}
+
+#[test]
+fn it_fails_when_the_limit_is_too_small() {
+    let result = create_thumbnail().to_data_uri(&LqipOptions {
+        max_bytes: 50,
+        ..Default::default()
+    });
+
+    assert!(matches!(result, Err(ThumbError::ExceedsByteBudget(50))));
+}
+
+#[test]
+fn it_blurs_the_placeholder() {
+    let thumbnail = create_thumbnail();
+    let roughness = |blur| {
+        let options = LqipOptions {
+            blur,
+            format: OutputFormat::Png(PngOptions::default()),
+            max_bytes: usize::MAX,
+            ..Default::default()
+        };
+        let image = decode(&thumbnail.to_data_uri(&options).unwrap(), "image/png").into_rgb8();
+        image
+            .rows()
+            .flat_map(|row| {
+                let row = row.collect::<Vec<_>>();
+                (1..row.len())
+                    .map(move |x| u32::from(row[x][1].abs_diff(row[x - 1][1])))
+                    .collect::<Vec<_>>()
+            })
+            .sum::<u32>()
+    };
+
+    assert!(roughness(2.0) < roughness(0.0));
+}
+
+#[test]
+fn it_resizes_with_the_options_of_the_thumbnail() {
+    let options = ThumbnailOptions {
+        filter: FilterType::Nearest,
+        ..Default::default()
+    };
+    let thumbnail = create_thumbnails_with_options(
+        Cursor::new(JPG_BYTES),
+        FileFormat::JointPhotographicExpertsGroup,
+        [ThumbnailSize::Medium],
+        &options,
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+    let mut png = Vec::new();
+    thumbnail
+        .encode(&OutputFormat::Png(PngOptions::default()), &mut png)
+        .unwrap();
+    let source = image::load_from_memory(&png).unwrap().into_rgb8();
+    let options = LqipOptions {
+        blur: 0.0,
+        format: OutputFormat::Png(PngOptions::default()),
+        max_bytes: usize::MAX,
+        ..Default::default()
+    };
+    let image = decode(&thumbnail.to_data_uri(&options).unwrap(), "image/png").into_rgb8();
+
+    // the nearest filter only picks colors of the thumbnail
+    assert!(image
+        .pixels()
+        .all(|pixel| source.pixels().any(|p| p == pixel)));
+}
+
+/// The placeholder is converted back to srgb as it is encoded without the color profile
+#[test]
+fn it_doesnt_embed_the_color_profile() {
+    let uri = p3_thumbnail()
+        .to_data_uri(&LqipOptions {
+            format: OutputFormat::Png(PngOptions::default()),
+            ..Default::default()
+        })
+        .unwrap();
+    let data = STANDARD
+        .decode(uri.strip_prefix("data:image/png;base64,").unwrap())
+        .unwrap();
+    let image = image::load_from_memory(&data).unwrap().into_rgb8();
+
+    assert!(!data.windows(4).any(|chunk| chunk == b"iCCP"));
+    assert_srgb_color(&image);
+}
+
+#[cfg(feature = "avif")]
+#[test]
+fn it_encodes_avif_data_uris_for_other_color_profiles() {
+    let uri = p3_thumbnail()
+        .to_data_uri(&LqipOptions {
+            format: OutputFormat::Avif(thumbnailer::AvifOptions::default()),
+            max_bytes: usize::MAX,
+            ..Default::default()
+        })
+        .unwrap();
+
+    assert!(uri.starts_with("data:image/avif;base64,"));
+}
+
+/// The srgb color (40, 180, 90) that is stored as (88, 177, 100) in the display p3 thumbnail
+const SRGB_COLOR: [u8; 3] = [40, 180, 90];
+
+fn p3_thumbnail() -> Thumbnail {
+    let mut png = Cursor::new(Vec::new());
+    image::RgbImage::from_pixel(64, 32, image::Rgb(SRGB_COLOR))
+        .write_to(&mut png, image::ImageFormat::Png)
+        .unwrap();
+    let options = ThumbnailOptions {
+        color_profile: ColorProfile::DisplayP3,
+        embed_color_profile: true,
+        ..Default::default()
+    };
+
+    create_thumbnails_with_options(
+        Cursor::new(png.into_inner()),
+        FileFormat::PortableNetworkGraphics,
+        [ThumbnailSize::Custom((64, 32))],
+        &options,
+    )
+    .unwrap()
+    .pop()
+    .unwrap()
+}
+
+fn assert_srgb_color(image: &image::RgbImage) {
+    for pixel in image.pixels() {
+        for (channel, expected) in pixel.0.into_iter().zip(SRGB_COLOR) {
+            assert!(channel.abs_diff(expected) <= 2, "{pixel:?}");
+        }
+    }
+}
+
+fn decode(uri: &str, mime: &str) -> DynamicImage {
+    let data = uri
+        .strip_prefix(&format!("data:{mime};base64,"))
+        .unwrap_or_else(|| panic!("{uri} isn't a {mime} data uri"));
+
+    image::load_from_memory(&STANDARD.decode(data).unwrap()).unwrap()
+}
+
+fn create_thumbnail() -> Thumbnail {
+    create_thumbnails(
+        Cursor::new(JPG_BYTES),
+        FileFormat::JointPhotographicExpertsGroup,
+        [ThumbnailSize::Medium],
+    )
+    .unwrap()
+    .pop()
+    .unwrap()
+}
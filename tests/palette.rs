@@ -0,0 +1,135 @@
+use file_format::FileFormat;
+use image::{ImageFormat, Rgba, RgbaImage};
+use std::io::Cursor;
+use thumbnailer::{
+    create_thumbnails, create_thumbnails_with_options, FilterType, Thumbnail, ThumbnailOptions,
+    ThumbnailSize,
+};
+
+const JPG_BYTES: &[u8] = include_bytes!("assets/test.jpg");
+// green stripes on a fully transparent background
+const TRANSPARENT_PNG_BYTES: &[u8] = include_bytes!("assets/test_transparent.png");
+
+#[test]
+fn it_extracts_palettes_sorted_by_weight() {
+    let thumbnail = create_thumbnail(JPG_BYTES);
+    let palette = thumbnail.palette(5);
+
+    assert_eq!(palette.len(), 5);
+    assert!(palette
+        .windows(2)
+        .all(|colors| colors[0].weight >= colors[1].weight));
+    let total = palette.iter().map(|color| color.weight).sum::<f32>();
+    assert!((total - 1.0).abs() < 1e-3);
+    assert_eq!(thumbnail.dominant_color(), Some(palette[0].color));
+}
+
+#[test]
+fn it_weights_the_colors_by_their_share() {
+    let mut image = RgbaImage::from_pixel(16, 16, Rgba([0, 0, 255, 255]));
+    for (x, _, pixel) in image.enumerate_pixels_mut() {
+        if x < 12 {
+            *pixel = Rgba([255, 0, 0, 255]);
+        }
+    }
+    // the nearest filter doesn't blend the colors at the edge between them
+    let options = ThumbnailOptions {
+        filter: FilterType::Nearest,
+        ..Default::default()
+    };
+    let palette = create_thumbnails_with_options(
+        Cursor::new(encode_png(&image)),
+        FileFormat::PortableNetworkGraphics,
+        [ThumbnailSize::Medium],
+        &options,
+    )
+    .unwrap()
+    .pop()
+    .unwrap()
+    .palette(2);
+
+    assert_eq!(palette.len(), 2);
+    assert_eq!(palette[0].color, [255, 0, 0]);
+    assert_eq!(palette[1].color, [0, 0, 255]);
+    assert!((palette[0].weight - 0.75).abs() < 0.05, "{palette:?}");
+}
+
+#[test]
+fn it_ignores_transparent_pixels() {
+    let color = create_thumbnail(TRANSPARENT_PNG_BYTES)
+        .dominant_color()
+        .unwrap();
+
+    assert!(color[1] > color[0] && color[1] > color[2], "{color:?}");
+}
+
+#[test]
+fn it_returns_fewer_colors_than_requested_for_uniform_images() {
+    let red = create_thumbnail(&png_bytes(Rgba([255, 0, 0, 255])));
+    let palette = red.palette(4);
+
+    assert_eq!(palette.len(), 1);
+    assert_eq!(palette[0].color, [255, 0, 0]);
+    assert!((palette[0].weight - 1.0).abs() < 1e-6);
+}
+
+/// Clusters that lose all their samples are moved to different samples,
+/// so noisy images with a few distinct colors get a palette without duplicates
+#[test]
+fn it_has_no_duplicate_colors() {
+    let mut state = 0x2545_f491_u32;
+    let mut random = move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state
+    };
+
+    for _ in 0..20 {
+        let colors = (0..6)
+            .map(|_| {
+                let [value, ..] = random().to_le_bytes();
+                Rgba([value, value, value, 255])
+            })
+            .collect::<Vec<_>>();
+        let image = RgbaImage::from_fn(16, 16, |_, _| colors[random() as usize % colors.len()]);
+        let palette = create_thumbnail(&encode_png(&image)).palette(8);
+        let mut distinct = palette.iter().map(|color| color.color).collect::<Vec<_>>();
+        distinct.sort_unstable();
+        distinct.dedup();
+
+        assert_eq!(distinct.len(), palette.len(), "{palette:?}");
+    }
+}
+
+#[test]
+fn it_has_no_colors_for_fully_transparent_images() {
+    let transparent = create_thumbnail(&png_bytes(Rgba([0, 0, 0, 0])));
+
+    assert!(transparent.palette(4).is_empty());
+    assert_eq!(transparent.dominant_color(), None);
+}
+
+fn png_bytes(color: Rgba<u8>) -> Vec<u8> {
+    encode_png(&RgbaImage::from_pixel(16, 16, color))
+}
+
+fn encode_png(image: &RgbaImage) -> Vec<u8> {
+    let mut buf = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)
+        .unwrap();
+
+    buf
+}
+
+fn create_thumbnail(bytes: &[u8]) -> Thumbnail {
+    create_thumbnails(
+        Cursor::new(bytes),
+        FileFormat::from_bytes(bytes),
+        [ThumbnailSize::Medium],
+    )
+    .unwrap()
+    .pop()
+    .unwrap()
+}
@@ -0,0 +1,82 @@
+use file_format::FileFormat;
+use std::io::Cursor;
+use thumbnailer::error::{ThumbError, ThumbResult};
+use thumbnailer::{create_pdf_page_thumbnails, create_thumbnails, Thumbnail, ThumbnailSize};
+
+const PDF_BYTES: &[u8] = include_bytes!("assets/test.pdf");
+// requires a user password to be opened
+const ENCRYPTED_PDF_BYTES: &[u8] = include_bytes!("assets/test_encrypted.pdf");
+// only restricts the permissions with an owner password and opens with an empty user password
+const OWNER_PASSWORD_PDF_BYTES: &[u8] = include_bytes!("assets/test_owner_password.pdf");
+
+#[test]
+fn it_creates_thumbnails_for_pdf() {
+    let result = create_pdf_thumbnails(PDF_BYTES);
+
+    match result {
+        Ok(thumbnails) => assert_eq!(thumbnails.len(), 3),
+        Err(ThumbError::Unsupported(_)) => {
+            // pdftoppm is not installed
+        }
+        Err(e) => panic!("failed to create thumbnails {e}"),
+    }
+}
+
+#[test]
+fn it_creates_thumbnails_for_a_chosen_pdf_page() {
+    let result = create_pdf_page_thumbnails(Cursor::new(PDF_BYTES), 1, [ThumbnailSize::Small]);
+
+    match result {
+        Ok(thumbnails) => assert_eq!(thumbnails[0].size(), (128, 128)),
+        Err(ThumbError::Unsupported(_)) => {
+            // pdftoppm is not installed
+        }
+        Err(e) => panic!("failed to create thumbnails {e}"),
+    }
+}
+
+#[test]
+fn it_rejects_encrypted_pdfs() {
+    let result = create_pdf_thumbnails(ENCRYPTED_PDF_BYTES);
+
+    match result {
+        Err(ThumbError::EncryptedPdf) => {}
+        Err(ThumbError::Unsupported(_)) => {
+            // pdftoppm is not installed
+        }
+        result => panic!("encrypted pdf wasn't rejected {result:?}"),
+    }
+}
+
+#[test]
+fn it_creates_thumbnails_for_pdfs_with_an_owner_password() {
+    let result = create_pdf_thumbnails(OWNER_PASSWORD_PDF_BYTES);
+
+    match result {
+        Ok(thumbnails) => assert_eq!(thumbnails.len(), 3),
+        Err(ThumbError::Unsupported(_)) => {
+            // pdftoppm is not installed
+        }
+        Err(e) => panic!("failed to create thumbnails {e}"),
+    }
+}
+
+#[test]
+fn it_rejects_malformed_pdfs() {
+    let result = create_pdf_thumbnails(b"this is not a pdf document");
+
+    assert!(matches!(result, Err(ThumbError::MalformedPdf)));
+}
+
+fn create_pdf_thumbnails(bytes: &[u8]) -> ThumbResult<Vec<Thumbnail>> {
+    let reader = Cursor::new(bytes);
+    create_thumbnails(
+        reader,
+        FileFormat::PortableDocumentFormat,
+        [
+            ThumbnailSize::Small,
+            ThumbnailSize::Medium,
+            ThumbnailSize::Large,
+        ],
+    )
+}
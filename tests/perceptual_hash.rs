@@ -0,0 +1,89 @@
+use file_format::FileFormat;
+use std::io::Cursor;
+use thumbnailer::{create_thumbnails, ImageHash, Thumbnail, ThumbnailSize};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+const JPG_BYTES: &[u8] = include_bytes!("assets/test.jpg");
+const WEBP_BYTES: &[u8] = include_bytes!("assets/test.webp");
+// black and white pixels alternating
+const CHECKERBOARD_BYTES: &[u8] = include_bytes!("assets/test_checkerboard.png");
+
+/// The maximum number of differing bits for the same image in different formats
+const MAX_DISTANCE: u32 = 8;
+
+type HashFn = fn(&Thumbnail) -> ImageHash;
+
+const HASHES: [(&str, HashFn); 3] = [
+    ("average", Thumbnail::average_hash),
+    ("difference", Thumbnail::difference_hash),
+    ("perceptual", Thumbnail::perceptual_hash),
+];
+
+#[test]
+fn it_hashes_the_same_image_in_different_formats_similarly() {
+    let thumbnails = [PNG_BYTES, JPG_BYTES, WEBP_BYTES].map(create_thumbnail);
+
+    for (name, hash) in HASHES {
+        let hashes = thumbnails.each_ref().map(hash);
+        for a in &hashes {
+            for b in &hashes {
+                assert!(
+                    a.distance(b) <= MAX_DISTANCE,
+                    "{name} hashes {a} and {b} differ by {}",
+                    a.distance(b)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn it_hashes_different_sizes_similarly() {
+    let thumbnails = create_thumbnails(
+        Cursor::new(JPG_BYTES),
+        FileFormat::JointPhotographicExpertsGroup,
+        [ThumbnailSize::Icon, ThumbnailSize::Large],
+    )
+    .unwrap();
+
+    for (name, hash) in HASHES {
+        let distance = hash(&thumbnails[0]).distance(&hash(&thumbnails[1]));
+        assert!(
+            distance <= MAX_DISTANCE,
+            "{name} hashes differ by {distance}"
+        );
+    }
+}
+
+#[test]
+fn it_hashes_different_images_differently() {
+    let photo = create_thumbnail(JPG_BYTES);
+    let checkerboard = create_thumbnail(CHECKERBOARD_BYTES);
+
+    for (name, hash) in HASHES {
+        let distance = hash(&photo).distance(&hash(&checkerboard));
+        assert!(
+            distance > MAX_DISTANCE,
+            "{name} hashes differ by {distance}"
+        );
+    }
+}
+
+#[test]
+fn it_counts_differing_bits() {
+    assert_eq!(ImageHash(0).distance(&ImageHash(0)), 0);
+    assert_eq!(ImageHash(0b1011).distance(&ImageHash(0b0110)), 3);
+    assert_eq!(ImageHash(0).distance(&ImageHash(u64::MAX)), 64);
+    assert_eq!(ImageHash(0xff).to_string(), "00000000000000ff");
+}
+
+fn create_thumbnail(bytes: &[u8]) -> Thumbnail {
+    create_thumbnails(
+        Cursor::new(bytes),
+        FileFormat::from_bytes(bytes),
+        [ThumbnailSize::Medium],
+    )
+    .unwrap()
+    .pop()
+    .unwrap()
+}
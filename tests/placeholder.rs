@@ -0,0 +1,116 @@
+use file_format::FileFormat;
+use image::RgbaImage;
+use std::io::Cursor;
+use thumbnailer::error::ThumbError;
+use thumbnailer::{
+    blurhash_to_thumbnail, create_thumbnails, create_thumbnails_with_placeholders,
+    thumbhash_to_thumbnail, Placeholders, Thumbnail, ThumbnailOptions, ThumbnailSize,
+};
+
+const JPG_BYTES: &[u8] = include_bytes!("assets/test.jpg");
+// green stripes on a fully transparent background
+const TRANSPARENT_PNG_BYTES: &[u8] = include_bytes!("assets/test_transparent.png");
+
+#[test]
+fn it_creates_placeholders_with_the_thumbnails() {
+    let (thumbnails, placeholders) = create_with_placeholders(JPG_BYTES);
+    let expected = create_thumbnails(
+        Cursor::new(JPG_BYTES),
+        FileFormat::JointPhotographicExpertsGroup,
+        [ThumbnailSize::Small, ThumbnailSize::Medium],
+    )
+    .unwrap();
+
+    assert_eq!(thumbnails.len(), 2);
+    for (thumbnail, expected) in thumbnails.iter().zip(&expected) {
+        assert_eq!(to_rgba8(thumbnail), to_rgba8(expected));
+    }
+    // four by three components with two characters each and the four character header
+    assert_eq!(placeholders.blurhash.len(), 4 + 2 * 4 * 3);
+}
+
+#[test]
+fn it_decodes_blurhashes() {
+    let (mut thumbnails, placeholders) = create_with_placeholders(JPG_BYTES);
+    let thumbnail = blurhash_to_thumbnail(&placeholders.blurhash, 32, 24).unwrap();
+
+    assert_eq!(thumbnail.size(), (32, 24));
+    let difference = mean_color(&to_rgba8(&thumbnail))
+        .iter()
+        .zip(mean_color(&to_rgba8(&thumbnails.pop().unwrap())))
+        .map(|(a, b)| (a - b).abs())
+        .fold(0f64, f64::max);
+    assert!(difference < 10.0, "mean colors differ by {difference}");
+}
+
+#[test]
+fn it_decodes_thumbhashes_with_the_aspect_ratio_of_the_source() {
+    let (_, placeholders) = create_with_placeholders(JPG_BYTES);
+    let thumbnail = thumbhash_to_thumbnail(&placeholders.thumbhash).unwrap();
+    let source = image::load_from_memory(JPG_BYTES).unwrap();
+    let (width, height) = thumbnail.size();
+
+    assert!(width <= 32 && height <= 32);
+    let ratio = width as f32 / height as f32;
+    let source_ratio = source.width() as f32 / source.height() as f32;
+    assert!(
+        (ratio - source_ratio).abs() < 0.2,
+        "{ratio} != {source_ratio}"
+    );
+}
+
+#[test]
+fn it_keeps_transparency_in_thumbhashes() {
+    let (_, placeholders) = create_with_placeholders(TRANSPARENT_PNG_BYTES);
+    let thumbnail = thumbhash_to_thumbnail(&placeholders.thumbhash).unwrap();
+
+    assert!(to_rgba8(&thumbnail).pixels().any(|pixel| pixel[3] < 255));
+}
+
+#[test]
+fn it_rejects_invalid_placeholders() {
+    assert!(matches!(
+        blurhash_to_thumbnail("invalid", 32, 32),
+        Err(ThumbError::InvalidPlaceholder(_))
+    ));
+    assert!(matches!(
+        thumbhash_to_thumbnail("not base64!"),
+        Err(ThumbError::InvalidPlaceholder(_))
+    ));
+    assert!(matches!(
+        thumbhash_to_thumbnail("AAAA"),
+        Err(ThumbError::InvalidPlaceholder(_))
+    ));
+}
+
+/// Averages the colors in linear light like the BlurHash dc component
+fn mean_color(image: &RgbaImage) -> [f64; 3] {
+    let to_linear = |value: u8| (f64::from(value) / 255.0).powf(2.2);
+    let mut sum = [0f64; 3];
+    for pixel in image.pixels() {
+        for channel in 0..3 {
+            sum[channel] += to_linear(pixel[channel]);
+        }
+    }
+    let count = f64::from(image.width() * image.height());
+
+    sum.map(|value| (value / count).powf(1.0 / 2.2) * 255.0)
+}
+
+fn to_rgba8(thumbnail: &Thumbnail) -> RgbaImage {
+    let mut buf = Vec::new();
+    thumbnail.clone().write_png(&mut buf).unwrap();
+
+    image::load_from_memory(&buf).unwrap().into_rgba8()
+}
+
+fn create_with_placeholders(bytes: &[u8]) -> (Vec<Thumbnail>, Placeholders) {
+    let format = FileFormat::from_bytes(bytes);
+    create_thumbnails_with_placeholders(
+        Cursor::new(bytes),
+        format,
+        [ThumbnailSize::Small, ThumbnailSize::Medium],
+        &ThumbnailOptions::default(),
+    )
+    .unwrap()
+}
@@ -0,0 +1,200 @@
+use file_format::FileFormat;
+use image::RgbaImage;
+use std::collections::HashSet;
+use std::io::Cursor;
+use thumbnailer::{
+    create_thumbnails, create_thumbnails_with_options, Adjustment, FilterType, OutputFormat,
+    PngFilter, PngOptions, PngQuantization, Thumbnail, ThumbnailOptions, ThumbnailSize,
+};
+
+const JPG_BYTES: &[u8] = include_bytes!("assets/test.jpg");
+// black and white pixels alternating
+const CHECKERBOARD_BYTES: &[u8] = include_bytes!("assets/test_checkerboard.png");
+// green stripes on a fully transparent background
+const TRANSPARENT_PNG_BYTES: &[u8] = include_bytes!("assets/test_transparent.png");
+
+#[test]
+fn it_stores_few_colors_in_a_packed_palette() {
+    // the nearest filter keeps the two colors of the checkerboard
+    let options = ThumbnailOptions {
+        filter: FilterType::Nearest,
+        ..Default::default()
+    };
+    let thumbnail = create_thumbnails_with_options(
+        Cursor::new(CHECKERBOARD_BYTES),
+        FileFormat::PortableNetworkGraphics,
+        [ThumbnailSize::Custom((32, 32))],
+        &options,
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+    let png = encode(&thumbnail, &PngOptions::default());
+
+    assert_eq!(
+        png_header(&png),
+        (png::ColorType::Indexed, png::BitDepth::One)
+    );
+    assert_eq!(decode(&png), to_rgba8(&thumbnail));
+}
+
+#[test]
+fn it_drops_unused_channels() {
+    let photo = create_thumbnail(JPG_BYTES, FileFormat::JointPhotographicExpertsGroup);
+    let png = encode(&photo, &PngOptions::default());
+    assert_eq!(
+        png_header(&png),
+        (png::ColorType::Rgb, png::BitDepth::Eight)
+    );
+    assert_eq!(decode(&png), to_rgba8(&photo));
+
+    let options = ThumbnailOptions {
+        adjustments: vec![Adjustment::Grayscale],
+        ..Default::default()
+    };
+    let gray = create_thumbnails_with_options(
+        Cursor::new(JPG_BYTES),
+        FileFormat::JointPhotographicExpertsGroup,
+        [ThumbnailSize::Medium],
+        &options,
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+    let png = encode(&gray, &PngOptions::default());
+    assert_eq!(
+        png_header(&png),
+        (png::ColorType::Grayscale, png::BitDepth::Eight)
+    );
+    assert_eq!(decode(&png), to_rgba8(&gray));
+}
+
+#[test]
+fn it_keeps_transparency_when_reducing() {
+    let thumbnail = create_thumbnail(TRANSPARENT_PNG_BYTES, FileFormat::PortableNetworkGraphics);
+    let png = encode(&thumbnail, &PngOptions::default());
+
+    assert_eq!(decode(&png), to_rgba8(&thumbnail));
+}
+
+#[test]
+fn it_writes_rgba_without_reducing() {
+    let photo = create_thumbnail(JPG_BYTES, FileFormat::JointPhotographicExpertsGroup);
+    let options = PngOptions {
+        reduce: false,
+        ..Default::default()
+    };
+    let png = encode(&photo, &options);
+
+    assert_eq!(
+        png_header(&png),
+        (png::ColorType::Rgba, png::BitDepth::Eight)
+    );
+}
+
+#[test]
+fn it_quantizes_to_a_palette() {
+    let photo = create_thumbnail(JPG_BYTES, FileFormat::JointPhotographicExpertsGroup);
+    let lossless = encode(&photo, &PngOptions::default());
+
+    for dithering in [0.0, 1.0] {
+        let options = PngOptions {
+            quantization: Some(PngQuantization {
+                colors: 16,
+                dithering,
+            }),
+            ..Default::default()
+        };
+        let png = encode(&photo, &options);
+        let image = decode(&png);
+        let colors = image.pixels().map(|pixel| pixel.0).collect::<HashSet<_>>();
+
+        assert_eq!(
+            png_header(&png),
+            (png::ColorType::Indexed, png::BitDepth::Four)
+        );
+        assert!(colors.len() <= 16);
+        assert!(png.len() < lossless.len() / 2);
+        assert!(mean_difference(&image, &to_rgba8(&photo)) < 12.0);
+    }
+}
+
+#[test]
+fn it_compresses_better_with_higher_levels() {
+    let photo = create_thumbnail(JPG_BYTES, FileFormat::JointPhotographicExpertsGroup);
+    let size = |compression| {
+        let options = PngOptions {
+            compression,
+            ..Default::default()
+        };
+        encode(&photo, &options).len()
+    };
+
+    assert!(size(9) <= size(1));
+    assert!(size(1) < size(0));
+}
+
+#[test]
+fn it_writes_identical_images_with_every_filter() {
+    let photo = create_thumbnail(JPG_BYTES, FileFormat::JointPhotographicExpertsGroup);
+    let expected = to_rgba8(&photo);
+
+    for filter in [
+        PngFilter::None,
+        PngFilter::Sub,
+        PngFilter::Up,
+        PngFilter::Average,
+        PngFilter::Paeth,
+        PngFilter::Adaptive,
+    ] {
+        let options = PngOptions {
+            filter,
+            ..Default::default()
+        };
+        assert_eq!(decode(&encode(&photo, &options)), expected, "{filter:?}");
+    }
+}
+
+fn mean_difference(a: &RgbaImage, b: &RgbaImage) -> f64 {
+    let sum: u64 = a
+        .as_raw()
+        .iter()
+        .zip(b.as_raw())
+        .map(|(a, b)| u64::from(a.abs_diff(*b)))
+        .sum();
+    sum as f64 / a.as_raw().len() as f64
+}
+
+fn png_header(bytes: &[u8]) -> (png::ColorType, png::BitDepth) {
+    let reader = png::Decoder::new(Cursor::new(bytes)).read_info().unwrap();
+    let info = reader.info();
+
+    (info.color_type, info.bit_depth)
+}
+
+fn encode(thumbnail: &Thumbnail, options: &PngOptions) -> Vec<u8> {
+    let mut buf = Vec::new();
+    thumbnail
+        .encode(&OutputFormat::Png(options.clone()), &mut buf)
+        .unwrap();
+
+    buf
+}
+
+fn decode(bytes: &[u8]) -> RgbaImage {
+    image::load_from_memory(bytes).unwrap().into_rgba8()
+}
+
+fn to_rgba8(thumbnail: &Thumbnail) -> RgbaImage {
+    let mut buf = Vec::new();
+    thumbnail.clone().write_png(&mut buf).unwrap();
+
+    decode(&buf)
+}
+
+fn create_thumbnail(bytes: &[u8], format: FileFormat) -> Thumbnail {
+    create_thumbnails(Cursor::new(bytes), format, [ThumbnailSize::Medium])
+        .unwrap()
+        .pop()
+        .unwrap()
+}
@@ -0,0 +1,96 @@
+use file_format::FileFormat;
+use image::RgbaImage;
+use std::io::Cursor;
+use thumbnailer::{create_thumbnails, create_thumbnails_unknown_type, ThumbnailSize};
+
+const PSD_BYTES: &[u8] = include_bytes!("assets/test.psd");
+const CMYK_PSD_BYTES: &[u8] = include_bytes!("assets/test_cmyk.psd");
+const THUMBNAIL_PSD_BYTES: &[u8] = include_bytes!("assets/test_thumbnail.psd");
+const MATTE_PSD_BYTES: &[u8] = include_bytes!("assets/test_matte.psd");
+const P3_PSD_BYTES: &[u8] = include_bytes!("assets/test_p3.psd");
+const CMYK_ICC_PSD_BYTES: &[u8] = include_bytes!("assets/test_cmyk_icc.psd");
+
+#[test]
+fn it_creates_thumbnails_for_psd() {
+    let image = create_psd_thumbnail(PSD_BYTES);
+
+    assert_eq!(image.get_pixel(4, 4).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(28, 4).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(4, 12)[3], 0);
+}
+
+#[test]
+fn it_creates_thumbnails_for_cmyk_psd() {
+    let image = create_psd_thumbnail(CMYK_PSD_BYTES);
+
+    assert_eq!(image.get_pixel(16, 8).0, [0, 255, 255, 255]);
+}
+
+/// The left half is red with half transparency matted against white
+#[test]
+fn it_unmattes_transparent_pixels() {
+    let image = create_psd_thumbnail(MATTE_PSD_BYTES);
+    let pixel = image.get_pixel(4, 8);
+
+    assert!(
+        pixel[0] > 250 && pixel[1] < 5 && pixel[2] < 5 && pixel[3].abs_diff(128) <= 1,
+        "{pixel:?}"
+    );
+    assert_eq!(image.get_pixel(28, 8).0, [0, 0, 255, 255]);
+}
+
+/// The fixture contains the display p3 color of the other color profile tests
+#[test]
+fn it_converts_psd_with_the_embedded_profile() {
+    let image = create_psd_thumbnail(P3_PSD_BYTES);
+    let pixel = image.get_pixel(16, 8);
+
+    for (channel, expected) in pixel.0.into_iter().zip([40, 180, 90, 255]) {
+        assert!(channel.abs_diff(expected) <= 2, "{pixel:?}");
+    }
+}
+
+/// The profile of the fixture maps half black ink to a lighter gray than the naive conversion
+#[test]
+fn it_converts_cmyk_psd_with_the_embedded_profile() {
+    let image = create_psd_thumbnail(CMYK_ICC_PSD_BYTES);
+    let paper = image.get_pixel(4, 8);
+    let gray = image.get_pixel(28, 8);
+
+    assert!(paper.0.iter().all(|&channel| channel > 250), "{paper:?}");
+    assert!(
+        gray.0[..3].iter().all(|channel| channel.abs_diff(191) <= 3),
+        "{gray:?}"
+    );
+}
+
+#[test]
+fn it_uses_the_embedded_thumbnail_without_composite() {
+    let image = create_psd_thumbnail(THUMBNAIL_PSD_BYTES);
+    let pixel = image.get_pixel(16, 8);
+
+    assert!(
+        pixel[0] < 50 && pixel[1] > 200 && pixel[2] < 50,
+        "{pixel:?}"
+    );
+}
+
+#[test]
+fn it_creates_thumbnails_for_psd_unknown() {
+    create_thumbnails_unknown_type(Cursor::new(PSD_BYTES), [ThumbnailSize::Small]).unwrap();
+}
+
+fn create_psd_thumbnail(bytes: &[u8]) -> RgbaImage {
+    let thumbnail = create_thumbnails(
+        Cursor::new(bytes),
+        FileFormat::AdobePhotoshopDocument,
+        [ThumbnailSize::Custom((32, 16))],
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+
+    image::load_from_memory(buf.get_ref()).unwrap().into_rgba8()
+}
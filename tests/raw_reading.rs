@@ -0,0 +1,133 @@
+use file_format::FileFormat;
+use std::io::Cursor;
+use thumbnailer::error::{ThumbError, ThumbResult};
+use thumbnailer::{create_thumbnails, create_thumbnails_unknown_type, Thumbnail, ThumbnailSize};
+
+const DNG_BYTES: &[u8] = include_bytes!("assets/test_raw.dng");
+const NEF_BYTES: &[u8] = include_bytes!("assets/test_raw.nef");
+const CR2_BYTES: &[u8] = include_bytes!("assets/test_raw.cr2");
+const CR3_BYTES: &[u8] = include_bytes!("assets/test_raw.cr3");
+const CR3_ROTATED_BYTES: &[u8] = include_bytes!("assets/test_raw_rotated.cr3");
+const CRW_BYTES: &[u8] = include_bytes!("assets/test_raw.crw");
+const RAF_BYTES: &[u8] = include_bytes!("assets/test_raw.raf");
+
+#[test]
+fn it_creates_thumbnails_for_dng() {
+    let thumbnail = create_raw_thumbnail(DNG_BYTES, FileFormat::TagImageFileFormat).unwrap();
+    assert_rotated_preview(thumbnail);
+}
+
+/// The sensor data of the nef fixture is a jpeg that is larger than the preview
+#[test]
+fn it_creates_thumbnails_for_nef() {
+    let thumbnail = create_raw_thumbnail(NEF_BYTES, FileFormat::NikonElectronicFile).unwrap();
+    assert_rotated_preview(thumbnail);
+    let thumbnail = create_raw_thumbnail(NEF_BYTES, FileFormat::TagImageFileFormat).unwrap();
+    assert_rotated_preview(thumbnail);
+}
+
+#[test]
+fn it_creates_thumbnails_for_cr2() {
+    let thumbnail = create_raw_thumbnail(CR2_BYTES, FileFormat::CanonRaw2).unwrap();
+    assert_rotated_preview(thumbnail);
+    let thumbnail = create_raw_thumbnail(CR2_BYTES, FileFormat::TagImageFileFormat).unwrap();
+    assert_rotated_preview(thumbnail);
+}
+
+#[test]
+fn it_creates_thumbnails_for_cr3() {
+    let thumbnail = create_raw_thumbnail(CR3_BYTES, FileFormat::CanonRaw3).unwrap();
+    assert_preview(thumbnail);
+}
+
+/// The orientation of the cr3 fixture is stored in the `CMT1` box instead of the preview
+#[test]
+fn it_creates_thumbnails_for_rotated_cr3() {
+    let thumbnail = create_raw_thumbnail(CR3_ROTATED_BYTES, FileFormat::CanonRaw3).unwrap();
+    assert_rotated_preview(thumbnail);
+}
+
+/// The orientation of the crw fixture is stored in the image info record of its heap
+#[test]
+fn it_creates_thumbnails_for_crw() {
+    let thumbnail = create_raw_thumbnail(CRW_BYTES, FileFormat::CanonRaw).unwrap();
+    assert_rotated_preview(thumbnail);
+    let mut thumbnails =
+        create_thumbnails_unknown_type(Cursor::new(CRW_BYTES), [ThumbnailSize::Icon]).unwrap();
+    assert_rotated_preview(thumbnails.pop().unwrap());
+}
+
+#[test]
+fn it_creates_thumbnails_for_raf() {
+    let thumbnail = create_raw_thumbnail(RAF_BYTES, FileFormat::FujifilmRaw).unwrap();
+    assert_preview(thumbnail);
+}
+
+/// The tiff contains the camera make and a small exif thumbnail that mustn't be used
+#[cfg(feature = "tiff")]
+#[test]
+fn it_reads_regular_tiffs_with_a_camera_make_at_full_size() {
+    let bytes = include_bytes!("assets/test_make.tiff");
+    let thumbnail = create_thumbnails(
+        Cursor::new(bytes),
+        FileFormat::TagImageFileFormat,
+        [ThumbnailSize::Custom((64, 48))],
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+    let image = image::load_from_memory(buf.get_ref()).unwrap().into_rgb8();
+
+    assert_eq!(image.dimensions(), (64, 48));
+    assert_eq!(image.get_pixel(32, 24).0, [20, 151, 13]);
+}
+
+#[test]
+fn it_creates_thumbnails_for_dng_unknown() {
+    let thumbnail = create_thumbnails_unknown_type(Cursor::new(DNG_BYTES), [ThumbnailSize::Icon])
+        .unwrap()
+        .pop()
+        .unwrap();
+    assert_rotated_preview(thumbnail);
+}
+
+#[test]
+fn it_fails_for_raws_without_preview() {
+    let result = create_raw_thumbnail(&DNG_BYTES[..0x6E], FileFormat::CanonRaw2);
+
+    assert!(matches!(result, Err(ThumbError::Decode)));
+}
+
+fn create_raw_thumbnail(bytes: &[u8], format: FileFormat) -> ThumbResult<Thumbnail> {
+    let reader = Cursor::new(bytes);
+    let mut thumbnails = create_thumbnails(reader, format, [ThumbnailSize::Icon])?;
+
+    Ok(thumbnails.pop().unwrap())
+}
+
+/// The largest preview is red on the left and blue on the right and
+/// has to be rotated clockwise according to the orientation of the raw file
+fn assert_rotated_preview(thumbnail: Thumbnail) {
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+    let image = image::load_from_memory(buf.get_ref()).unwrap().into_rgb8();
+    let top = image.get_pixel(32, 8);
+    let bottom = image.get_pixel(32, 56);
+
+    assert!(top[0] > 200 && top[2] < 50, "top is {top:?}");
+    assert!(bottom[2] > 200 && bottom[0] < 50, "bottom is {bottom:?}");
+}
+
+/// The largest preview is red on the left and blue on the right
+fn assert_preview(thumbnail: Thumbnail) {
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+    let image = image::load_from_memory(buf.get_ref()).unwrap().into_rgb8();
+    let left = image.get_pixel(8, 32);
+    let right = image.get_pixel(56, 32);
+
+    assert!(left[0] > 200 && left[2] < 50, "left is {left:?}");
+    assert!(right[2] > 200 && right[0] < 50, "right is {right:?}");
+}
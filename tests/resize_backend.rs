@@ -0,0 +1,88 @@
+#![cfg(feature = "fast_resize")]
+
+use file_format::FileFormat;
+use std::io::Cursor;
+use thumbnailer::{
+    create_thumbnails_with_options, FilterType, ResizeBackend, ThumbnailOptions, ThumbnailSize,
+};
+
+// rgba, rgba with hidden colors, grayscale and 16 bit images
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+const TRANSPARENT_BYTES: &[u8] = include_bytes!("assets/test_transparent.png");
+const CHECKERBOARD_BYTES: &[u8] = include_bytes!("assets/test_checkerboard.png");
+const PNG16_BYTES: &[u8] = include_bytes!("assets/test16.png");
+
+#[test]
+fn it_resizes_within_tolerance_of_the_image_backend() {
+    for bytes in [
+        PNG_BYTES,
+        TRANSPARENT_BYTES,
+        CHECKERBOARD_BYTES,
+        PNG16_BYTES,
+    ] {
+        for filter in [
+            FilterType::Nearest,
+            FilterType::Triangle,
+            FilterType::CatmullRom,
+            FilterType::Gaussian,
+            FilterType::Lanczos3,
+        ] {
+            for linear_light in [false, true] {
+                let image = create_image(bytes, filter.clone(), linear_light, ResizeBackend::Image);
+                let simd = create_image(bytes, filter.clone(), linear_light, ResizeBackend::Simd);
+                let difference = mean_difference(&image, &simd);
+
+                assert!(
+                    difference < 1.0,
+                    "{filter:?} (linear light {linear_light}) differs by {difference}"
+                );
+            }
+        }
+    }
+}
+
+/// Compares the premultiplied pixels, as the color of fully transparent pixels isn't visible
+fn mean_difference(a: &image::RgbaImage, b: &image::RgbaImage) -> f64 {
+    assert_eq!(a.dimensions(), b.dimensions());
+    let premultiplied = |pixel: &image::Rgba<u8>| {
+        let [r, g, b, a] = pixel.0.map(f64::from);
+        [r * a / 255.0, g * a / 255.0, b * a / 255.0, a]
+    };
+    let total: f64 = a
+        .pixels()
+        .zip(b.pixels())
+        .flat_map(|(a, b)| {
+            let (a, b) = (premultiplied(a), premultiplied(b));
+            (0..4).map(move |i| (a[i] - b[i]).abs())
+        })
+        .sum();
+
+    total / (a.width() * a.height() * 4) as f64
+}
+
+fn create_image(
+    bytes: &[u8],
+    filter: FilterType,
+    linear_light: bool,
+    backend: ResizeBackend,
+) -> image::RgbaImage {
+    let options = ThumbnailOptions {
+        filter,
+        linear_light,
+        backend,
+        ..Default::default()
+    };
+    let thumbnail = create_thumbnails_with_options(
+        Cursor::new(bytes),
+        FileFormat::PortableNetworkGraphics,
+        [ThumbnailSize::Custom((100, 60))],
+        &options,
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+
+    image::load_from_memory(buf.get_ref()).unwrap().into_rgba8()
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "svg")]
+
+use file_format::FileFormat;
+use std::io::Cursor;
+use thumbnailer::error::ThumbResult;
+use thumbnailer::{create_thumbnails, create_thumbnails_unknown_type, Thumbnail, ThumbnailSize};
+
+const SVG_BYTES: &[u8] = include_bytes!("assets/test.svg");
+
+#[test]
+fn it_creates_thumbnails_for_svg() {
+    let thumbnails = create_svg_thumbnails([
+        ThumbnailSize::Icon,
+        ThumbnailSize::Small,
+        ThumbnailSize::Medium,
+        ThumbnailSize::Large,
+    ])
+    .unwrap();
+    let sizes: Vec<(u32, u32)> = thumbnails.iter().map(Thumbnail::size).collect();
+
+    assert_eq!(sizes, vec![(64, 32), (128, 64), (256, 128), (512, 256)]);
+}
+
+#[test]
+fn it_keeps_the_aspect_ratio_for_svg() {
+    let thumbnail = create_svg_thumbnails([ThumbnailSize::Custom((100, 400))])
+        .unwrap()
+        .pop()
+        .unwrap();
+
+    assert_eq!(thumbnail.size(), (100, 50));
+}
+
+#[test]
+fn it_renders_svg_with_unconstrained_sides() {
+    let thumbnails =
+        create_svg_thumbnails([ThumbnailSize::Height(100), ThumbnailSize::Width(100)]).unwrap();
+    let sizes: Vec<(u32, u32)> = thumbnails.iter().map(Thumbnail::size).collect();
+
+    assert_eq!(sizes, vec![(200, 100), (100, 50)]);
+}
+
+#[test]
+fn it_keeps_transparency_for_svg() {
+    let thumbnail = create_svg_thumbnails([ThumbnailSize::Medium])
+        .unwrap()
+        .pop()
+        .unwrap();
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+    let image = image::load_from_memory(buf.get_ref()).unwrap().into_rgba8();
+
+    assert_eq!(image.get_pixel(0, 0)[3], 0);
+    assert_eq!(image.get_pixel(64, 64)[3], 255);
+    assert!((120..=135).contains(&image.get_pixel(192, 64)[3]));
+}
+
+#[test]
+fn it_creates_thumbnails_for_svg_unknown() {
+    let thumbnail = create_thumbnails_unknown_type(Cursor::new(SVG_BYTES), [ThumbnailSize::Small])
+        .unwrap()
+        .pop()
+        .unwrap();
+
+    assert_eq!(thumbnail.size(), (128, 64));
+}
+
+fn create_svg_thumbnails<I: IntoIterator<Item = ThumbnailSize>>(
+    sizes: I,
+) -> ThumbResult<Vec<Thumbnail>> {
+    let reader = Cursor::new(SVG_BYTES);
+    create_thumbnails(reader, FileFormat::ScalableVectorGraphics, sizes)
+}
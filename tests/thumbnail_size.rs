@@ -0,0 +1,180 @@
+use file_format::FileFormat;
+use std::io::Cursor;
+use thumbnailer::error::ThumbError;
+use thumbnailer::freedesktop::CacheSize;
+use thumbnailer::{create_thumbnails, ThumbnailSize};
+
+const JPG_BYTES: &[u8] = include_bytes!("assets/test.jpg");
+
+#[test]
+fn it_parses_sizes() {
+    for (text, size) in [
+        ("256x256", ThumbnailSize::Custom((256, 256))),
+        ("320x240", ThumbnailSize::Custom((320, 240))),
+        ("w320", ThumbnailSize::Width(320)),
+        ("h240", ThumbnailSize::Height(240)),
+        ("512", ThumbnailSize::MaxEdge(512)),
+        ("icon", ThumbnailSize::Icon),
+        ("medium", ThumbnailSize::Medium),
+        ("large", ThumbnailSize::Large),
+        (
+            "freedesktop-normal",
+            ThumbnailSize::Freedesktop(CacheSize::Normal),
+        ),
+        (
+            "freedesktop-large",
+            ThumbnailSize::Freedesktop(CacheSize::Large),
+        ),
+        (
+            "freedesktop-x-large",
+            ThumbnailSize::Freedesktop(CacheSize::XLarge),
+        ),
+        (
+            "freedesktop-xx-large",
+            ThumbnailSize::Freedesktop(CacheSize::XXLarge),
+        ),
+    ] {
+        assert_eq!(text.parse::<ThumbnailSize>().unwrap(), size, "{text}");
+        assert_eq!(size.to_string(), text);
+    }
+}
+
+#[test]
+fn it_parses_scale_factors() {
+    for (text, size) in [
+        ("medium@2x", ThumbnailSize::Custom((512, 512))),
+        ("Large@2x", ThumbnailSize::Custom((1024, 1024))),
+        ("freedesktop-large@2x", ThumbnailSize::MaxEdge(512)),
+        ("256x128@2x", ThumbnailSize::Custom((512, 256))),
+        ("w320@3x", ThumbnailSize::Width(960)),
+        ("h100@1x", ThumbnailSize::Height(100)),
+    ] {
+        assert_eq!(text.parse::<ThumbnailSize>().unwrap(), size, "{text}");
+    }
+}
+
+#[test]
+fn it_round_trips_sizes() {
+    for size in [
+        ThumbnailSize::Icon,
+        ThumbnailSize::Small,
+        ThumbnailSize::Medium,
+        ThumbnailSize::Large,
+        ThumbnailSize::Larger,
+        ThumbnailSize::Custom((320, 240)),
+        ThumbnailSize::Freedesktop(CacheSize::Normal),
+        ThumbnailSize::Freedesktop(CacheSize::Large),
+        ThumbnailSize::Freedesktop(CacheSize::XLarge),
+        ThumbnailSize::Freedesktop(CacheSize::XXLarge),
+        ThumbnailSize::Width(320),
+        ThumbnailSize::Height(240),
+        ThumbnailSize::MaxEdge(512),
+    ] {
+        assert_eq!(size.to_string().parse::<ThumbnailSize>().unwrap(), size);
+    }
+}
+
+#[test]
+fn it_rejects_invalid_sizes() {
+    for text in [
+        "",
+        "abc",
+        "0x5",
+        "5x",
+        "w",
+        "w-1",
+        "large@x",
+        "large@0x",
+        "large@2",
+        "normal",
+        "freedesktop-huge",
+    ] {
+        assert!(
+            matches!(
+                text.parse::<ThumbnailSize>(),
+                Err(ThumbError::InvalidSize(_))
+            ),
+            "{text}"
+        );
+    }
+}
+
+#[test]
+fn it_rejects_oversized_sizes() {
+    for text in [
+        "w4294967295",
+        "h8193",
+        "8193",
+        "8193x1",
+        "1024x1024@9x",
+        "larger@9x",
+    ] {
+        assert!(
+            matches!(
+                text.parse::<ThumbnailSize>(),
+                Err(ThumbError::InvalidSize(_))
+            ),
+            "{text}"
+        );
+    }
+    assert_eq!(
+        "w8192".parse::<ThumbnailSize>().unwrap(),
+        ThumbnailSize::Width(8192)
+    );
+}
+
+#[test]
+fn it_scales_sizes() {
+    assert_eq!(
+        ThumbnailSize::Medium.scaled(2),
+        ThumbnailSize::Custom((512, 512))
+    );
+    assert_eq!(ThumbnailSize::Medium.to_string(), "medium");
+    assert_eq!(
+        ThumbnailSize::Freedesktop(CacheSize::XXLarge).scaled(2),
+        ThumbnailSize::MaxEdge(2048)
+    );
+}
+
+#[test]
+fn it_keeps_the_aspect_ratio_of_the_source() {
+    let source = (1000, 500);
+
+    assert_eq!(ThumbnailSize::Width(320).dimensions_for(source), (320, 160));
+    assert_eq!(
+        ThumbnailSize::Height(100).dimensions_for(source),
+        (200, 100)
+    );
+    assert_eq!(
+        ThumbnailSize::MaxEdge(100).dimensions_for(source),
+        (100, 50)
+    );
+    assert_eq!(
+        ThumbnailSize::Freedesktop(CacheSize::Normal).dimensions_for((500, 1000)),
+        (64, 128)
+    );
+    assert_eq!(ThumbnailSize::Medium.dimensions_for(source), (256, 256));
+}
+
+#[test]
+fn it_creates_thumbnails_with_unconstrained_sides() {
+    let source = image::load_from_memory(JPG_BYTES).unwrap();
+    let thumbnails = create_thumbnails(
+        Cursor::new(JPG_BYTES),
+        FileFormat::JointPhotographicExpertsGroup,
+        [
+            ThumbnailSize::Width(100),
+            ThumbnailSize::Height(100),
+            ThumbnailSize::Freedesktop(CacheSize::Normal),
+        ],
+    )
+    .unwrap();
+    let expected = |size: ThumbnailSize| size.dimensions_for((source.width(), source.height()));
+
+    assert_eq!(thumbnails[0].size().0, 100);
+    assert_eq!(thumbnails[0].size(), expected(ThumbnailSize::Width(100)));
+    assert_eq!(thumbnails[1].size().1, 100);
+    assert_eq!(thumbnails[1].size(), expected(ThumbnailSize::Height(100)));
+    let (width, height) = thumbnails[2].size();
+    assert_eq!(width.max(height), 128);
+}
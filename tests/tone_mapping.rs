@@ -0,0 +1,207 @@
+use file_format::FileFormat;
+use image::ColorType;
+use std::io::Cursor;
+use thumbnailer::{
+    create_thumbnails, create_thumbnails_with_options, Thumbnail, ThumbnailOptions, ThumbnailSize,
+    ToneMapping,
+};
+
+const PNG16_BYTES: &[u8] = include_bytes!("assets/test16.png");
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+
+#[test]
+fn it_keeps_16_bit_png_output() {
+    let thumbnail = create_thumbnail(PNG16_BYTES, FileFormat::PortableNetworkGraphics);
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png16(&mut buf).unwrap();
+    let image = image::load_from_memory(buf.get_ref()).unwrap();
+
+    assert_eq!(image.color(), ColorType::Rgba16);
+}
+
+#[test]
+fn it_writes_8_bit_png16_for_8_bit_sources() {
+    let thumbnail = create_thumbnail(PNG_BYTES, FileFormat::PortableNetworkGraphics);
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png16(&mut buf).unwrap();
+    let image = image::load_from_memory(buf.get_ref()).unwrap();
+
+    assert_eq!(image.color(), ColorType::Rgba8);
+}
+
+/// The pq code value of sdr reference white
+const PQ_SDR_WHITE: u16 = 38055;
+
+#[test]
+fn it_tone_maps_16_bit_pq_pngs() {
+    let options = ThumbnailOptions {
+        tone_mapping: ToneMapping::Clamp,
+        ..Default::default()
+    };
+    let hdr = create_image(&pq_png(PQ_SDR_WHITE, true), &options);
+    let sdr = create_image(&pq_png(PQ_SDR_WHITE, false), &options);
+
+    // sdr reference white is mapped to white and isn't clipped
+    assert!(hdr.pixels().all(|pixel| pixel.0 == [255; 3]), "{hdr:?}");
+    // without the cicp chunk the code values are shown as they are
+    assert!(
+        sdr.pixels().all(|pixel| pixel[0].abs_diff(148) <= 1),
+        "{sdr:?}"
+    );
+}
+
+/// The fixture is a 10 bit avif filled with sdr reference white that signals
+/// pq and bt.2020 in its av1 sequence header
+#[cfg(feature = "avif")]
+#[test]
+fn it_tone_maps_pq_avifs() {
+    const PQ_AVIF_BYTES: &[u8] = include_bytes!("assets/test_pq.avif");
+
+    let options = ThumbnailOptions {
+        tone_mapping: ToneMapping::Clamp,
+        ..Default::default()
+    };
+    let image = create_image_with_format(PQ_AVIF_BYTES, FileFormat::Av1ImageFileFormat, &options);
+
+    assert!(
+        image
+            .pixels()
+            .all(|pixel| pixel.0.iter().all(|&value| value >= 253)),
+        "{image:?}"
+    );
+}
+
+#[cfg(feature = "hdr")]
+#[test]
+fn it_tone_maps_with_the_operator_of_the_options() {
+    const HDR_BYTES: &[u8] = include_bytes!("assets/test_bright.hdr");
+
+    let highlight = |tone_mapping| {
+        let options = ThumbnailOptions {
+            tone_mapping,
+            ..Default::default()
+        };
+        let image = create_thumbnails_with_options(
+            Cursor::new(HDR_BYTES),
+            FileFormat::RadianceHdr,
+            [ThumbnailSize::Custom((32, 16))],
+            &options,
+        )
+        .unwrap()
+        .pop()
+        .unwrap();
+        let mut buf = Cursor::new(Vec::new());
+        image.write_png(&mut buf).unwrap();
+        image::load_from_memory(buf.get_ref()).unwrap().into_rgb8()[(28, 8)][0]
+    };
+
+    assert_eq!(highlight(ToneMapping::Clamp), 255);
+    assert!(highlight(ToneMapping::Reinhard) < 255);
+}
+
+/// Linear 0.5 is encoded as 188 in srgb and would be darkened by the default operator
+#[cfg(feature = "hdr")]
+#[test]
+fn it_doesnt_tone_map_sdr_float_images() {
+    let mut hdr = Vec::new();
+    image::codecs::hdr::HdrEncoder::new(&mut hdr)
+        .encode(&[image::Rgb([0.5; 3]); 32 * 16], 32, 16)
+        .unwrap();
+    let thumbnail = create_thumbnail(&hdr, FileFormat::RadianceHdr);
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+    let image = image::load_from_memory(buf.get_ref()).unwrap().into_rgb8();
+
+    assert!(
+        image.pixels().all(|pixel| pixel[0].abs_diff(188) <= 1),
+        "{:?}",
+        image.get_pixel(0, 0)
+    );
+}
+
+#[cfg(feature = "hdr")]
+#[test]
+fn it_tone_maps_hdr_images() {
+    const HDR_BYTES: &[u8] = include_bytes!("assets/test_bright.hdr");
+
+    for tone_mapping in [
+        ToneMapping::Clamp,
+        ToneMapping::Reinhard,
+        ToneMapping::Aces,
+        ToneMapping::Hable,
+    ] {
+        let mut thumbnail = create_thumbnail(HDR_BYTES, FileFormat::RadianceHdr);
+        thumbnail.set_tone_mapping(tone_mapping);
+        let mut buf = Cursor::new(Vec::new());
+        thumbnail.write_png(&mut buf).unwrap();
+        let image = image::load_from_memory(buf.get_ref()).unwrap().into_rgb8();
+        let grey = image.get_pixel(4, 8)[0];
+        let highlight = image.get_pixel(28, 8)[0];
+
+        assert!(grey > 50, "{tone_mapping:?} grey is {grey}");
+        assert!(
+            highlight > grey,
+            "{tone_mapping:?} highlight is {highlight}"
+        );
+        if tone_mapping == ToneMapping::Clamp {
+            assert_eq!(highlight, 255);
+        } else {
+            assert!(highlight < 255, "{tone_mapping:?} clips the highlight");
+        }
+    }
+}
+
+/// Encodes a grey 16 bit png with the code value that signals pq and bt.2020 if requested
+fn pq_png(value: u16, cicp: bool) -> Vec<u8> {
+    let mut buf = Vec::new();
+    let mut encoder = png::Encoder::new(&mut buf, 4, 4);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Sixteen);
+    let mut writer = encoder.write_header().unwrap();
+    if cicp {
+        // bt.2020 primaries, pq transfer, rgb matrix and full range
+        writer
+            .write_chunk(png::chunk::ChunkType(*b"cICP"), &[9, 16, 0, 1])
+            .unwrap();
+    }
+    let data = value.to_be_bytes().repeat(4 * 4 * 3);
+    writer.write_image_data(&data).unwrap();
+    writer.finish().unwrap();
+
+    buf
+}
+
+fn create_image(bytes: &[u8], options: &ThumbnailOptions) -> image::RgbImage {
+    create_image_with_format(bytes, FileFormat::PortableNetworkGraphics, options)
+}
+
+fn create_image_with_format(
+    bytes: &[u8],
+    format: FileFormat,
+    options: &ThumbnailOptions,
+) -> image::RgbImage {
+    let thumbnail = create_thumbnails_with_options(
+        Cursor::new(bytes),
+        format,
+        [ThumbnailSize::Custom((4, 4))],
+        options,
+    )
+    .unwrap()
+    .pop()
+    .unwrap();
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.write_png(&mut buf).unwrap();
+
+    image::load_from_memory(buf.get_ref()).unwrap().into_rgb8()
+}
+
+fn create_thumbnail(bytes: &[u8], format: FileFormat) -> Thumbnail {
+    create_thumbnails(
+        Cursor::new(bytes),
+        format,
+        [ThumbnailSize::Custom((32, 16))],
+    )
+    .unwrap()
+    .pop()
+    .unwrap()
+}
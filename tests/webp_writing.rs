@@ -0,0 +1,121 @@
+#![cfg(feature = "webp")]
+
+use file_format::FileFormat;
+use image::RgbaImage;
+use std::io::Cursor;
+use thumbnailer::{create_thumbnails, Thumbnail, ThumbnailSize, WebpOptions};
+
+const PNG_BYTES: &[u8] = include_bytes!("assets/test.png");
+// green stripes on a fully transparent background
+const TRANSPARENT_PNG_BYTES: &[u8] = include_bytes!("assets/test_transparent.png");
+
+#[test]
+fn it_writes_smaller_files_with_lower_quality() {
+    let thumbnail = create_thumbnail(PNG_BYTES);
+    let high = write_webp(&thumbnail, &quality(90.0));
+    let low = write_webp(&thumbnail, &quality(10.0));
+
+    assert!(
+        low.len() < high.len(),
+        "quality 10 produced {} bytes and quality 90 {} bytes",
+        low.len(),
+        high.len()
+    );
+    let original = to_rgba8(&thumbnail);
+    assert!(mean_difference(&original, &decode(&low)) < 16.0);
+    assert!(mean_difference(&original, &decode(&high)) < 4.0);
+}
+
+#[test]
+fn it_writes_lossless_images() {
+    let thumbnail = create_thumbnail(PNG_BYTES);
+    let options = WebpOptions {
+        lossless: true,
+        method: 6,
+        ..Default::default()
+    };
+    let webp = write_webp(&thumbnail, &options);
+
+    assert_eq!(decode(&webp), to_rgba8(&thumbnail));
+}
+
+#[test]
+fn it_keeps_the_alpha_channel() {
+    let thumbnail = create_thumbnail(TRANSPARENT_PNG_BYTES);
+    let original = to_rgba8(&thumbnail);
+
+    for lossless in [false, true] {
+        let options = WebpOptions {
+            lossless,
+            ..Default::default()
+        };
+        let image = decode(&write_webp(&thumbnail, &options));
+
+        for (pixel, expected) in image.pixels().zip(original.pixels()) {
+            assert!(
+                pixel[3].abs_diff(expected[3]) <= 2,
+                "expected alpha {} but got {} in lossless {lossless}",
+                expected[3],
+                pixel[3]
+            );
+        }
+    }
+}
+
+fn quality(quality: f32) -> WebpOptions {
+    WebpOptions {
+        quality,
+        ..Default::default()
+    }
+}
+
+fn mean_difference(a: &RgbaImage, b: &RgbaImage) -> f64 {
+    let sum: u64 = a
+        .as_raw()
+        .iter()
+        .zip(b.as_raw())
+        .map(|(a, b)| u64::from(a.abs_diff(*b)))
+        .sum();
+    sum as f64 / a.as_raw().len() as f64
+}
+
+fn write_webp(thumbnail: &Thumbnail, options: &WebpOptions) -> Vec<u8> {
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.clone().write_webp(&mut buf, options).unwrap();
+
+    buf.into_inner()
+}
+
+fn decode(bytes: &[u8]) -> RgbaImage {
+    let image = image::load_from_memory_with_format(bytes, image::ImageFormat::WebP).unwrap();
+    clear_transparent_pixels(image.into_rgba8())
+}
+
+fn to_rgba8(thumbnail: &Thumbnail) -> RgbaImage {
+    let mut buf = Cursor::new(Vec::new());
+    thumbnail.clone().write_png(&mut buf).unwrap();
+
+    clear_transparent_pixels(image::load_from_memory(buf.get_ref()).unwrap().into_rgba8())
+}
+
+/// The encoder is free to change the color of invisible pixels
+fn clear_transparent_pixels(mut image: RgbaImage) -> RgbaImage {
+    for pixel in image.pixels_mut() {
+        if pixel[3] == 0 {
+            pixel.0 = [0; 4];
+        }
+    }
+
+    image
+}
+
+fn create_thumbnail(bytes: &[u8]) -> Thumbnail {
+    create_thumbnails(
+        Cursor::new(bytes),
+        FileFormat::PortableNetworkGraphics,
+        [ThumbnailSize::Custom((64, 64))],
+    )
+    .unwrap()
+    .pop()
+    .unwrap()
+}